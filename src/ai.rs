@@ -1,84 +1,250 @@
-use types::{Category, DiceCategory, Entry, MyEntry};
-use types::Category::*;
-use dice::{Dice, MyDice, DICE_NUM, subsets, all_tosses};
+use types::{Category, ColumnEntry, Entry, MyEntry};
+use dice::{Dice, subsets};
 use player::Player;
-use result::{Result, BONUS_REACH, BONUS_VALUE};
+use probability::{TossTable, dice_chance};
+use result::Result;
+use scorecard::Scorecard;
+use table::TableView;
 use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+/// points a toss saved for a later turn is estimated to be worth
+const SAVED_TOSS_VALUE: f32 = 3.0;
+/// evaluation of dice that end the game with a win
+const INSTANT_WIN_VALUE: f32 = 10000.0;
+
+/// How hard the `YahtzeeAI` plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    /// keeps the biggest group of equal dice and writes the highest value
+    Easy,
+    /// rerolls like `Hard` but writes the highest value
+    Medium,
+    /// looks ahead for the rerolls and the upper bonus
+    Hard,
+}
+
+impl Difficulty {
+    pub fn by_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "medium" => Some(Difficulty::Medium),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
 
 pub struct YahtzeeAI {
     name: String,
+    difficulty: Difficulty,
+    /// the toss table of the first game's dice
+    tosses: OnceLock<TossTable>,
 }
 
 impl YahtzeeAI {
     pub fn new() -> YahtzeeAI {
-        YahtzeeAI { name: String::from("KI") }
+        YahtzeeAI::with_difficulty("KI", Difficulty::Hard)
+    }
+
+    pub fn with_difficulty(name: &str, difficulty: Difficulty) -> YahtzeeAI {
+        YahtzeeAI { name: String::from(name), difficulty, tosses: OnceLock::new() }
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+}
+
+impl Default for YahtzeeAI {
+    fn default() -> YahtzeeAI {
+        YahtzeeAI::new()
     }
 }
 
 impl Player for YahtzeeAI {
-    fn decide_keep(&self, result: &Result, remaining_tosses: u8, dice: &Dice) -> Dice {
-        let mut keeps: Vec<(Dice, f32)> = subsets(dice).into_iter().map(
-            |subset| {
-                let eval = YahtzeeAI::evaluate_keep(result, remaining_tosses, dice, &subset); 
-                print!("{:?}, {} - ", subset, eval);
-                (subset, eval)
-            }
-        ).collect();
-        println!();
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice {
+        let card = table.card();
+        if self.difficulty == Difficulty::Easy {
+            return YahtzeeAI::biggest_group(dice)
+        }
+        let mut keeps = self.evaluate_keeps(card, remaining_tosses, dice);
         keeps.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Less).reverse());
         keeps.remove(0).0
     }
 
-    fn decide_entry(&self, result: &Result, dice: &Dice) -> Entry {
-        let mut entries: Vec<_> = Category::into_iter().filter(|&&cat| !result.has(cat)).map(|&cat| {
-            let entry = dice.entry_of_category(cat);
-            let eval = YahtzeeAI::evaluate_entry(result, &entry); 
-            print!("{:?}, {} - ", entry, eval);
+    fn decide_entry(&self, table: &TableView, dice: &Dice) -> ColumnEntry {
+        let card = table.card();
+        if self.difficulty != Difficulty::Hard {
+            return YahtzeeAI::highest_entry(card, dice)
+        }
+        let mut entries: Vec<_> = card.possible_entries(dice).into_iter().map(|entry| {
+            let (column, ref column_entry) = entry;
+            let eval = YahtzeeAI::evaluate_entry(card.column(column), column_entry)
+                * card.weight(column) as f32;
             (entry, eval)
         }).collect();
-        println!();
-        entries.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Less).reverse());
+        // stable, so equal evaluations go to the column with the lowest weight
+        entries.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(Ordering::Less).reverse());
         entries.remove(0).0
     }
 
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("ai:{}:{}", self.difficulty.name(), self.name))
+    }
+
+    fn announce(&self, table: &TableView, dice: &Dice) -> Option<(usize, Category)> {
+        let card = table.card();
+        if self.difficulty == Difficulty::Easy {
+            return card.announceable().first().cloned().filter(|_| card.must_announce())
+        }
+        let remaining_tosses = card.rules().toss_num - 1;
+        let mut best = None;
+        let mut best_value = if card.must_announce() {
+            f32::MIN
+        } else {
+            self.best_keep_value(card, remaining_tosses, dice)
+        };
+        for (column, category) in card.announceable() {
+            let mut announced = card.clone();
+            announced.announce(column, category);
+            let value = self.best_keep_value(&announced, remaining_tosses, dice);
+            if value > best_value {
+                best_value = value;
+                best = Some((column, category));
+            }
+        }
+        best
+    }
 }
 
 impl YahtzeeAI {
-    fn evaluate_keep(result: &Result, remaining_tosses: u8, dice: &Dice, keep: &Dice) -> f32 {
-        let mut value = 0f32;
-        let tosses = all_tosses(DICE_NUM - keep.len() as u8); 
-        let mut tosses_num = 1;
-        value += YahtzeeAI::evaluate_value(result, &dice) as f32;
-        for (toss, times) in tosses.iter() {
-            let mut future_dice = keep.clone();
-            future_dice.extend(toss);
-            value += YahtzeeAI::evaluate_value(result, &future_dice) * *times as f32;
-            tosses_num += times;
+    /// every keep of the dice with its evaluation, keeping all dice ends the
+    /// rolling
+    fn evaluate_keeps(&self, card: &Scorecard, remaining_tosses: u8, dice: &Dice) -> Vec<(Dice, f32)> {
+        let faces = &card.rules().dice_faces;
+        let cached = self.tosses.get_or_init(|| TossTable::new(faces, dice.len()));
+        let other;
+        let tosses = if cached.fits(faces, dice.len()) {
+            cached
+        } else {
+            other = TossTable::new(faces, dice.len());
+            &other
+        };
+        let rerolls = YahtzeeAI::evaluate_rerolls(card, tosses, remaining_tosses.max(1));
+        subsets(dice).into_iter().map(|keep| {
+            let eval = match tosses.keep(&keep) {
+                Some(index) if keep.len() < dice.len() => rerolls[index] as f32,
+                _ => YahtzeeAI::evaluate_card(card, dice),
+            };
+            (keep, eval)
+        }).collect()
+    }
+
+    /// Expected evaluation of rolling every keep of the table, when the
+    /// best dice are kept after each of the later tosses as well.
+    fn evaluate_rerolls(card: &Scorecard, tosses: &TossTable, remaining_tosses: u8) -> Vec<f64> {
+        let saved_toss = YahtzeeAI::saved_toss_value(card) as f64;
+        let mut next_card = card.clone();
+        next_card.set_toss(card.toss() + remaining_tosses);
+        let mut values: Vec<f64> = tosses.outcomes().iter()
+            .map(|dice| YahtzeeAI::evaluate_card(&next_card, dice) as f64)
+            .collect();
+        // back from the last toss to the one after the current dice
+        for toss in (1..remaining_tosses).rev() {
+            next_card.set_toss(card.toss() + toss);
+            let keep_values = tosses.keep_values(&values);
+            values = tosses.outcomes().iter().enumerate().map(|(outcome, dice)| {
+                tosses.outcome_keeps(outcome).iter()
+                    .filter(|&&keep| tosses.keeps()[keep].len() < dice.len())
+                    .map(|&keep| keep_values[keep] - saved_toss)
+                    .fold(YahtzeeAI::evaluate_card(&next_card, dice) as f64, f64::max)
+            }).collect();
+        }
+        tosses.keep_values(&values).into_iter().map(|value| value - saved_toss).collect()
+    }
+
+    /// a toss that is not used now can only help in one of the later turns
+    fn saved_toss_value(card: &Scorecard) -> f32 {
+        if card.rules().save_rolls && card.open() > 1 {
+            SAVED_TOSS_VALUE
+        } else {
+            0f32
         }
-        value / tosses_num as f32
     }
 
-    fn evaluate_value(result: &Result, dice: &Dice) -> f32 {
+    /// best weighted evaluation over all boxes the dice may be written to
+    fn evaluate_card(card: &Scorecard, dice: &Dice) -> f32 {
+        if card.wins_instantly(dice) {
+            return INSTANT_WIN_VALUE
+        }
+        let entries = card.possible_entries(dice);
+        let only_chance = entries.iter().all(|&(_, (cat, _))| cat == Category::Chance);
         let mut max = 0f32;
-        for &cat in Category::into_iter() {
-            if result.has(cat) || cat == Category::Chance {
+        for (column, entry) in entries {
+            if entry.0 == Category::Chance && !only_chance {
                 continue
             }
-            let value = YahtzeeAI::evaluate_entry(result, &dice.entry_of_category(cat));
+            let result = card.column(column);
+            let mut value = YahtzeeAI::evaluate_entry(result, &entry);
+            if result.earns_yahtzee_bonus(dice) {
+                value += result.rules().yahtzee_bonus_value as f32;
+            }
+            value *= card.weight(column) as f32;
             if value > max {
-                max = value.into();
+                max = value;
             }
         }
         max
     }
 
+    /// all dice of the most frequent face, the higher face on a tie
+    fn biggest_group(dice: &Dice) -> Dice {
+        let mut best = Dice::new();
+        for &die in dice {
+            let group: Dice = dice.iter().cloned().filter(|&d| d == die).collect();
+            if group.len() > best.len() || (group.len() == best.len() && best.first() < Some(&die)) {
+                best = group;
+            }
+        }
+        best
+    }
+
+    /// the entry with the most weighted points, the first one on a tie
+    fn highest_entry(card: &Scorecard, dice: &Dice) -> ColumnEntry {
+        let entries = card.possible_entries(dice);
+        let mut best = entries[0];
+        for &entry in &entries[1..] {
+            let (column, (_, value)) = entry;
+            if value as u32 * card.weight(column) > (best.1).1 as u32 * card.weight(best.0) {
+                best = entry;
+            }
+        }
+        best
+    }
+
+    fn best_keep_value(&self, card: &Scorecard, remaining_tosses: u8, dice: &Dice) -> f32 {
+        self.evaluate_keeps(card, remaining_tosses, dice).into_iter()
+            .map(|(_, eval)| eval)
+            .fold(0f32, f32::max)
+    }
+
     fn evaluate_entry(result: &Result, entry: &Entry) -> f32 {
         let value = entry.1 as f32;
         if entry.is_upper() && !result.has_bonus() {
-            let above_bonus_points = entry.upper_above_bonus() as f32;
+            let above_bonus_points = entry.upper_above_bonus(result.rules()) as f32;
             // let bonus_to_go = BONUS_REACH - result.value_upper();
             // let needed_percent = value / bonus_to_go as f32;
             // value + needed_percent * BONUS_VALUE as f32
@@ -89,9 +255,52 @@ impl YahtzeeAI {
     }
 }
 
+/// chance that `dice_num` six-sided dice show the wanted ones within the
+/// tries, see `probability::dice_chance`
 pub fn probability_dice(dice_num: u8, wanted_dice: &Dice, tries: u8) -> f32 {
     if dice_num < wanted_dice.len() as u8 {
         return 0f32;
     }
-    unimplemented!()
+    dice_chance(&[1, 2, 3, 4, 5, 6], dice_num, &Dice::new(), wanted_dice, tries) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::GameState;
+    use rules::RuleSet;
+    use types::Category::*;
+
+    #[test]
+    fn test_easy() {
+        let ai = YahtzeeAI::with_difficulty("Easy", Difficulty::Easy);
+        let states = vec![GameState::new(&RuleSet::yahtzee())];
+        let table = TableView::new(&states, &[], 0, 0);
+        assert_eq!(ai.decide_keep(&table, 2, &vec![1, 2, 2, 5, 5]), vec![5, 5]);
+        assert_eq!(ai.decide_keep(&table, 2, &vec![1, 4, 4, 4, 5]), vec![4, 4, 4]);
+        assert_eq!(ai.decide_entry(&table, &vec![2, 3, 4, 5, 6]), (0, (LargeStraght, 40)));
+    }
+
+    #[test]
+    fn test_lookahead() {
+        let ai = YahtzeeAI::with_difficulty("Hard", Difficulty::Hard);
+        let rules = RuleSet { categories: vec![Chance], ..RuleSet::yahtzee() };
+        let states = vec![GameState::new(&rules)];
+        let table = TableView::new(&states, &[], 0, 0);
+        // a four is only worth keeping for the last toss
+        assert_eq!(ai.decide_keep(&table, 2, &vec![1, 1, 1, 4, 4]), vec![]);
+        assert_eq!(ai.decide_keep(&table, 1, &vec![1, 1, 1, 4, 4]), vec![4, 4]);
+        assert_eq!(ai.decide_keep(&table, 2, &vec![1, 1, 1, 5, 6]), vec![5, 6]);
+        // three tosses of all dice are a whole turn, see the solver
+        let tosses = TossTable::new(&rules.dice_faces, 5);
+        let rerolls = YahtzeeAI::evaluate_rerolls(table.card(), &tosses, 3);
+        let start = rerolls[tosses.keep(&vec![]).unwrap()];
+        assert!((start - 23.3338).abs() < 1e-3, "{}", start);
+    }
+
+    #[test]
+    fn test_probability_dice() {
+        assert!((probability_dice(1, &vec![6], 2) - 11.0 / 36.0).abs() < 1e-6);
+        assert_eq!(probability_dice(2, &vec![1, 1, 1], 3), 0.0);
+    }
 }
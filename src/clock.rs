@@ -0,0 +1,113 @@
+use dice::Dice;
+use player::Player;
+use table::TableView;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long the players may think.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimeControl {
+    /// for every single decision
+    pub per_decision: Option<Duration>,
+    /// for all decisions of a seat in the game together
+    pub per_game: Option<Duration>,
+}
+
+impl TimeControl {
+    pub fn is_unlimited(&self) -> bool {
+        self.per_decision.is_none() && self.per_game.is_none()
+    }
+}
+
+/// Keeps the time every seat used and stops players that take too long.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    control: TimeControl,
+    used: Vec<Duration>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, seats: usize) -> Clock {
+        Clock { control, used: vec![Duration::from_secs(0); seats] }
+    }
+
+    pub fn control(&self) -> TimeControl {
+        self.control
+    }
+
+    /// time the seat thought in the game so far
+    pub fn used(&self, seat: usize) -> Duration {
+        self.used[seat]
+    }
+
+    pub fn set_used(&mut self, seat: usize, used: Duration) {
+        self.used[seat] = used;
+    }
+
+    /// time the seat has for its next decision, `None` without a limit
+    pub fn limit(&self, seat: usize) -> Option<Duration> {
+        let left = self.control.per_game.map(|game| game.checked_sub(self.used[seat]).unwrap_or_default());
+        match (self.control.per_decision, left) {
+            (Some(decision), Some(left)) => Some(decision.min(left)),
+            (decision, left) => decision.or(left),
+        }
+    }
+
+    /// Asks the player on a thread of its own and waits at most the limit of
+    /// the seat. `None` if the player did not answer in time, it is told to
+    /// cancel and its answer is thrown away once it comes.
+    pub(crate) fn decide<T, F>(&mut self, player: &Arc<dyn Player>, table: &TableView, dice: &Dice, decide: F)
+        -> Option<T>
+        where T: Send + 'static,
+              F: FnOnce(&dyn Player, &TableView, &Dice) -> T + Send + 'static {
+        let seat = table.seat();
+        let limit = match self.limit(seat) {
+            Some(limit) => limit,
+            None => return Some(decide(&**player, table, dice)),
+        };
+        if limit == Duration::from_secs(0) {
+            return None
+        }
+        let (sender, receiver) = mpsc::channel();
+        let thinking = Arc::clone(player);
+        let states = table.states().to_vec();
+        let names = table.names().to_vec();
+        let turn = table.turn();
+        let dice = dice.clone();
+        let start = Instant::now();
+        thread::spawn(move || {
+            let table = TableView::new(&states, &names, seat, turn);
+            // the game may have stopped waiting
+            sender.send(decide(&*thinking, &table, &dice)).ok();
+        });
+        let answer = receiver.recv_timeout(limit).ok();
+        if answer.is_none() {
+            player.cancel();
+        }
+        self.used[seat] += start.elapsed().min(limit);
+        answer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit() {
+        let mut clock = Clock::new(TimeControl::default(), 2);
+        assert_eq!(clock.limit(0), None);
+        clock = Clock::new(TimeControl {
+            per_decision: Some(Duration::from_millis(100)),
+            per_game: Some(Duration::from_millis(250)),
+        }, 2);
+        assert_eq!(clock.limit(0), Some(Duration::from_millis(100)));
+        clock.set_used(0, Duration::from_millis(200));
+        assert_eq!(clock.limit(0), Some(Duration::from_millis(50)));
+        clock.set_used(0, Duration::from_millis(300));
+        assert_eq!(clock.limit(0), Some(Duration::from_secs(0)));
+        assert_eq!(clock.limit(1), Some(Duration::from_millis(100)));
+    }
+}
@@ -1,9 +1,6 @@
 use types::Value;
 use std::cmp::Ordering;
 
-pub const DICE_NUM: u8 = 5;
-pub const DICE_FACES: [Die; 6] = [1,2,3,4,5,6];
-
 pub type Die = u8;
 pub type Dice = Vec<Die>;
 
@@ -50,7 +47,7 @@ pub fn is_permutation<T: Clone + Ord>(a: &[T], b: &[T]) -> bool {
 }
 
 ///both sorted
-pub fn is_subset<T: Ord>(subset: &Vec<T>, superset: &Vec<T>) -> bool {
+pub fn is_subset<T: Ord>(subset: &[T], superset: &[T]) -> bool {
     let mut sub_iter = subset.iter();
     let mut cur_a = sub_iter.next();
     for b in superset {
@@ -63,7 +60,7 @@ pub fn is_subset<T: Ord>(subset: &Vec<T>, superset: &Vec<T>) -> bool {
             continue;
         }
     }
-    return cur_a.is_none();
+    cur_a.is_none()
 }
 
 pub fn subsets<T: Clone + Ord>(orig: &[T]) -> Vec<Vec<T>> {
@@ -84,7 +81,7 @@ pub fn subsets<T: Clone + Ord>(orig: &[T]) -> Vec<Vec<T>> {
 }
 
 fn _subsets<T: Clone>(orig: &[T]) -> Vec<Vec<T>> {
-    if orig.len() == 0 {
+    if orig.is_empty() {
         vec![vec![]]
     } else {
         let mut result = Vec::new();
@@ -142,47 +139,50 @@ pub fn variations_with_repetition_hist<T: Clone + Ord>(orig: &[T], size: usize)
             cnt += 1;
         }
     }
+    history.push((last, cnt));
     history
 }
 
-pub fn all_tosses(num_dice: u8) -> Vec<(Dice, usize)> {
-    variations_with_repetition_hist(&DICE_FACES , num_dice as usize)
+pub fn all_tosses(faces: &[Die], num_dice: u8) -> Vec<(Dice, usize)> {
+    variations_with_repetition_hist(faces, num_dice as usize)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const DICE_FACES: [Die; 6] = [1,2,3,4,5,6];
+
     #[test]
     fn test_is_permutation() {
-        let ref vec = vec![1, 2, 5, 5, 6];
-        assert!(is_permutation(vec, &vec![1,2,5,5,6]));
-        assert!(is_permutation(vec, &vec![2,1,5,5,6]));
-        assert!(is_permutation(vec, &vec![5,2,1,5,6]));
-        assert!(is_permutation(vec, &vec![5,2,5,1,6]));
-        assert!(is_permutation(vec, &vec![6,2,5,5,1]));
-        assert!(is_permutation(vec, &vec![1,5,2,5,6]));
-        assert!(is_permutation(vec, &vec![1,5,5,2,6]));
-        assert!(is_permutation(vec, &vec![1,6,5,5,2]));
+        let vec = &[1, 2, 5, 5, 6];
+        assert!(is_permutation(vec, &[1,2,5,5,6]));
+        assert!(is_permutation(vec, &[2,1,5,5,6]));
+        assert!(is_permutation(vec, &[5,2,1,5,6]));
+        assert!(is_permutation(vec, &[5,2,5,1,6]));
+        assert!(is_permutation(vec, &[6,2,5,5,1]));
+        assert!(is_permutation(vec, &[1,5,2,5,6]));
+        assert!(is_permutation(vec, &[1,5,5,2,6]));
+        assert!(is_permutation(vec, &[1,6,5,5,2]));
     }
 
     #[test]
     fn test_is_subset() {
-        let ref super_set = vec![1, 2, 5, 5, 6];
-        assert!(is_subset(&vec![], super_set));
-        assert!(is_subset(&vec![1], super_set));
-        assert!(is_subset(&vec![1, 5], super_set));
-        assert!(is_subset(&vec![1, 5, 5], super_set));
-        assert!(is_subset(&vec![1, 5, 6], super_set));
-        assert!(is_subset(&vec![2, 5, 5], super_set));
-        assert!(is_subset(&vec![2, 5, 6], super_set));
-        assert!(is_subset(&vec![5], super_set));
-        assert!(is_subset(&vec![6], super_set));
+        let super_set = &[1, 2, 5, 5, 6];
+        assert!(is_subset(&[], super_set));
+        assert!(is_subset(&[1], super_set));
+        assert!(is_subset(&[1, 5], super_set));
+        assert!(is_subset(&[1, 5, 5], super_set));
+        assert!(is_subset(&[1, 5, 6], super_set));
+        assert!(is_subset(&[2, 5, 5], super_set));
+        assert!(is_subset(&[2, 5, 6], super_set));
+        assert!(is_subset(&[5], super_set));
+        assert!(is_subset(&[6], super_set));
     }
 
     #[test]
     fn test_subsets() {
-        let ref vec = vec![1, 2, 5, 5, 6];
+        let vec = &vec![1, 2, 5, 5, 6];
         assert!(subsets(vec).contains(vec));
         assert!(subsets(vec).contains(&vec![1]));
         assert!(subsets(vec).contains(&vec![1, 2]));
@@ -206,10 +206,10 @@ mod tests {
 
     #[test]
     fn test_subsets_size_4_with_double() {
-        let ref vec = vec![1,2,3,3];
-        let ref subs = vec![vec![], vec![1], vec![2], vec![3], vec![1,2],
-                        vec![1,3], vec![2,3], vec![3,3], vec![1,2,3],
-                        vec![1,3,3], vec![2,3,3], vec![1,2,3,3]];
+        let vec = &[1,2,3,3];
+        let subs = &[vec![], vec![1], vec![2], vec![3], vec![1,2],
+                     vec![1,3], vec![2,3], vec![3,3], vec![1,2,3],
+                     vec![1,3,3], vec![2,3,3], vec![1,2,3,3]];
         println!("{:?}", subsets(vec));
         assert!(is_permutation(&subsets(vec), subs));
     }
@@ -226,7 +226,12 @@ mod tests {
     #[test]
     fn test_variation_with_repetition() {
         let variations = variations_with_repetition(&DICE_FACES, 3);
-        unimplemented!()
+        assert_eq!(variations.len(), 6 * 6 * 6);
+        assert!(variations.contains(&vec![1,1,2]));
+        assert!(variations.contains(&vec![2,1,1]));
+        assert!(variations.contains(&vec![6,3,5]));
+        assert!(is_permutation(&variations[..6], &[vec![1,1,1], vec![1,1,2], vec![1,1,3],
+                                                    vec![1,1,4], vec![1,1,5], vec![1,1,6]]));
     }
 
     #[test]
@@ -236,5 +241,7 @@ mod tests {
         assert!(variations.contains(&(vec![1,1,2], 3)));
         assert!(variations.contains(&(vec![1,1,5], 3)));
         assert!(variations.contains(&(vec![1,3,5], 6)));
+        assert_eq!(variations.last(), Some(&(vec![6,6,6], 1)));
+        assert_eq!(variations.iter().map(|&(_, cnt)| cnt).sum::<usize>(), 6 * 6 * 6);
     }
 }
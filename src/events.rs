@@ -0,0 +1,257 @@
+use dice::Dice;
+use rules::RuleSet;
+use session::RuleViolation;
+use types::{Category, ColumnEntry};
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+
+/// Everything that happens during a game, in order.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    GameStarted { players: Vec<String> },
+    TurnStarted { turn: usize, seat: usize },
+    Toss { seat: usize, toss: u8, dice: Dice },
+    Keep { seat: usize, keep: Dice },
+    Announced { seat: usize, column: usize, category: Category },
+    EntryWritten { seat: usize, entry: ColumnEntry },
+    /// the upper section of a column reached the bonus
+    BonusReached { seat: usize, column: usize },
+    YahtzeeBonus { seat: usize, column: usize },
+    /// a decision was refused, `retry` if the player is asked again
+    Violation { seat: usize, violation: RuleViolation, retry: bool },
+    Forfeited { seat: usize },
+    Disqualified { seat: usize },
+    /// the last decision of the seat was taken back
+    Undone { seat: usize },
+    /// an undone decision of the seat was made again
+    Redone { seat: usize },
+    /// the seat ran out of time and the clock decides for it
+    TimedOut { seat: usize },
+    /// a Generala with the first toss ends the game
+    InstantWin { seat: usize },
+    /// the player of the seat could not get ready as asked
+    Warning { seat: usize, message: String },
+    /// final score of every seat, `None` if disqualified
+    GameOver { scores: Vec<Option<u32>> },
+}
+
+impl GameEvent {
+    /// one line JSON object
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        match self {
+            GameEvent::GameStarted { players } => {
+                let names: Vec<String> = players.iter().map(|name| json_string(name)).collect();
+                write!(json, "\"event\":\"game_started\",\"players\":[{}]", names.join(","))
+            }
+            GameEvent::TurnStarted { turn, seat } =>
+                write!(json, "\"event\":\"turn_started\",\"turn\":{},\"seat\":{}", turn, seat),
+            GameEvent::Toss { seat, toss, dice } =>
+                write!(json, "\"event\":\"toss\",\"seat\":{},\"toss\":{},\"dice\":{:?}", seat, toss, dice),
+            GameEvent::Keep { seat, keep } =>
+                write!(json, "\"event\":\"keep\",\"seat\":{},\"keep\":{:?}", seat, keep),
+            GameEvent::Announced { seat, column, category } =>
+                write!(json, "\"event\":\"announced\",\"seat\":{},\"column\":{},\"category\":\"{:?}\"",
+                       seat, column, category),
+            GameEvent::EntryWritten { seat, entry: (column, (category, value)) } =>
+                write!(json, "\"event\":\"entry_written\",\"seat\":{},\"column\":{},\"category\":\"{:?}\",\"value\":{}",
+                       seat, column, category, value),
+            GameEvent::BonusReached { seat, column } =>
+                write!(json, "\"event\":\"bonus_reached\",\"seat\":{},\"column\":{}", seat, column),
+            GameEvent::YahtzeeBonus { seat, column } =>
+                write!(json, "\"event\":\"yahtzee_bonus\",\"seat\":{},\"column\":{}", seat, column),
+            GameEvent::Violation { seat, violation, retry } =>
+                write!(json, "\"event\":\"violation\",\"seat\":{},\"violation\":{},\"retry\":{}",
+                       seat, json_string(&violation.to_string()), retry),
+            GameEvent::Forfeited { seat } =>
+                write!(json, "\"event\":\"forfeited\",\"seat\":{}", seat),
+            GameEvent::Disqualified { seat } =>
+                write!(json, "\"event\":\"disqualified\",\"seat\":{}", seat),
+            GameEvent::Undone { seat } =>
+                write!(json, "\"event\":\"undone\",\"seat\":{}", seat),
+            GameEvent::Redone { seat } =>
+                write!(json, "\"event\":\"redone\",\"seat\":{}", seat),
+            GameEvent::TimedOut { seat } =>
+                write!(json, "\"event\":\"timed_out\",\"seat\":{}", seat),
+            GameEvent::InstantWin { seat } =>
+                write!(json, "\"event\":\"instant_win\",\"seat\":{}", seat),
+            GameEvent::Warning { seat, message } =>
+                write!(json, "\"event\":\"warning\",\"seat\":{},\"message\":{}", seat, json_string(message)),
+            GameEvent::GameOver { scores } => {
+                let scores: Vec<String> = scores.iter()
+                    .map(|score| score.map_or(String::from("null"), |s| s.to_string()))
+                    .collect();
+                write!(json, "\"event\":\"game_over\",\"scores\":[{}]", scores.join(","))
+            }
+        }.expect("writing to a String");
+        json.push('}');
+        json
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub trait EventSink {
+    fn event(&mut self, event: &GameEvent);
+}
+
+/// Discards all events.
+pub struct SilentSink;
+
+impl EventSink for SilentSink {
+    fn event(&mut self, _event: &GameEvent) {}
+}
+
+/// Prints a readable log of the game to stdout.
+#[derive(Default)]
+pub struct ConsoleSink {
+    players: Vec<String>,
+    last_turn: Option<usize>,
+    columns: usize,
+}
+
+impl ConsoleSink {
+    pub fn new() -> ConsoleSink {
+        ConsoleSink::default()
+    }
+
+    /// a sink that names the column of a box when the rules have several
+    pub fn for_rules(rules: &RuleSet) -> ConsoleSink {
+        ConsoleSink { columns: rules.columns.len(), ..ConsoleSink::default() }
+    }
+
+    fn column(&self, column: usize) -> String {
+        if self.columns > 1 {
+            format!(" in column {}", column + 1)
+        } else {
+            String::new()
+        }
+    }
+
+    fn name(&self, seat: usize) -> &str {
+        self.players.get(seat).map_or("?", |name| name.as_str())
+    }
+}
+
+impl EventSink for ConsoleSink {
+    fn event(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::GameStarted { players } => {
+                self.players = players.clone();
+                println!("Start Game");
+            }
+            GameEvent::TurnStarted { turn, seat } => {
+                if self.last_turn != Some(*turn) {
+                    println!("Turn {}", turn);
+                    self.last_turn = Some(*turn);
+                }
+                println!("Player {}", self.name(*seat));
+            }
+            GameEvent::Toss { toss, dice, .. } => println!("Toss {}: {:?}", toss, dice),
+            GameEvent::Keep { keep, .. } => println!("keep {:?}", keep),
+            GameEvent::Announced { column, category, .. } =>
+                println!("Announce {:?}{}", category, self.column(*column)),
+            GameEvent::EntryWritten { entry: (column, (category, value)), .. } =>
+                println!("Write {} to {:?}{}", value, category, self.column(*column)),
+            GameEvent::BonusReached { seat, column } =>
+                println!("Player {} reached the bonus{}", self.name(*seat), self.column(*column)),
+            GameEvent::YahtzeeBonus { .. } => println!("Yahtzee bonus!"),
+            GameEvent::Violation { seat, violation, retry } => {
+                if *retry {
+                    println!("Player {} {}, try again", self.name(*seat), violation);
+                } else {
+                    println!("Player {} {}", self.name(*seat), violation);
+                }
+            }
+            GameEvent::Forfeited { seat } => println!("Player {} forfeits the turn", self.name(*seat)),
+            GameEvent::Disqualified { seat } => println!("Player {} is disqualified", self.name(*seat)),
+            GameEvent::Undone { seat } => println!("Player {} takes back a move", self.name(*seat)),
+            GameEvent::Redone { seat } => println!("Player {} makes a move again", self.name(*seat)),
+            GameEvent::TimedOut { seat } => println!("Player {} is out of time", self.name(*seat)),
+            GameEvent::InstantWin { seat } =>
+                println!("Player {} wins with a served Generala", self.name(*seat)),
+            GameEvent::Warning { seat, message } => println!("Player {}: {}", self.name(*seat), message),
+            GameEvent::GameOver { scores } => {
+                for (seat, score) in scores.iter().enumerate() {
+                    match score {
+                        Some(score) => println!("Score of Player {}: {}", self.name(seat), score),
+                        None => println!("Player {} is disqualified", self.name(seat)),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Writes every event as one JSON object per line.
+pub struct JsonLinesSink<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(out: W) -> JsonLinesSink<W> {
+        JsonLinesSink { out }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> EventSink for JsonLinesSink<W> {
+    fn event(&mut self, event: &GameEvent) {
+        let result: io::Result<()> = writeln!(self.out, "{}", event.to_json())
+            .and_then(|_| self.out.flush());
+        if let Err(err) = result {
+            eprintln!("Can not write event: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+
+    #[test]
+    fn test_json() {
+        assert_eq!(GameEvent::Toss { seat: 1, toss: 2, dice: vec![1, 3, 3, 5, 6] }.to_json(),
+                   r#"{"event":"toss","seat":1,"toss":2,"dice":[1, 3, 3, 5, 6]}"#);
+        assert_eq!(GameEvent::EntryWritten { seat: 0, entry: (0, (FullHouse, 25)) }.to_json(),
+                   r#"{"event":"entry_written","seat":0,"column":0,"category":"FullHouse","value":25}"#);
+        assert_eq!(GameEvent::GameOver { scores: vec![Some(230), None] }.to_json(),
+                   r#"{"event":"game_over","scores":[230,null]}"#);
+        assert_eq!(GameEvent::GameStarted { players: vec![String::from("A \"B\"")] }.to_json(),
+                   r#"{"event":"game_started","players":["A \"B\""]}"#);
+        assert_eq!(GameEvent::Warning { seat: 1, message: String::from("no table") }.to_json(),
+                   r#"{"event":"warning","seat":1,"message":"no table"}"#);
+    }
+
+    #[test]
+    fn test_console_column() {
+        assert_eq!(ConsoleSink::for_rules(&RuleSet::yahtzee()).column(0), "");
+        assert_eq!(ConsoleSink::for_rules(&RuleSet::triple_yahtzee()).column(2), " in column 3");
+    }
+
+    #[test]
+    fn test_json_lines_sink() {
+        let mut sink = JsonLinesSink::new(Vec::new());
+        sink.event(&GameEvent::Forfeited { seat: 0 });
+        sink.event(&GameEvent::Disqualified { seat: 1 });
+        let out = String::from_utf8(sink.into_inner()).unwrap();
+        assert_eq!(out, "{\"event\":\"forfeited\",\"seat\":0}\n{\"event\":\"disqualified\",\"seat\":1}\n");
+    }
+}
@@ -0,0 +1,217 @@
+//! A player that is another program. Every decision is one line to its
+//! standard input, answered by one line on its standard output:
+//!
+//! ```text
+//! announce 11455 Ones=3 Fives@2=15     ->  Chance@4 or -
+//! keep 2 11455 Ones=3 Fives@2=15       ->  55, an empty line rerolls all
+//! entry 12355 Ones=3 Fives@2=15        ->  Fives
+//! ```
+//!
+//! After the kind come the remaining tosses for a keep, the dice and the
+//! filled boxes of the card in the notation of a game record. An answer
+//! that can not be read, or none from a program that is gone, gets the same
+//! default as a player out of time, but only the clock marks a timeout in
+//! the record.
+
+use dice::Dice;
+use notation::{parse_box, write_box, write_dice};
+use player::Player;
+use session::{timeout_announcement, timeout_entry};
+use table::TableView;
+use types::{Category, ColumnEntry};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+struct Engine {
+    child: Child,
+    input: ChildStdin,
+    /// the lines of the output, read on a thread of their own
+    answers: Arc<Mutex<Receiver<String>>>,
+    /// a request is still unanswered
+    waiting: bool,
+}
+
+impl Engine {
+    /// starts the program, the command is split at whitespace
+    fn start(command: &str) -> io::Result<Engine> {
+        let mut args = command.split_whitespace();
+        let program = args.next().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let input = child.stdin.take().expect("piped stdin");
+        let output = BufReader::new(child.stdout.take().expect("piped stdout"));
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in output.lines().map_while(|line| line.ok()) {
+                if sender.send(line).is_err() {
+                    break
+                }
+            }
+        });
+        Ok(Engine { child, input, answers: Arc::new(Mutex::new(receiver)), waiting: false })
+    }
+
+    fn stop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// A player that is another program. It answers one request at a time: a
+/// request while the last one is unanswered means the game stopped waiting
+/// for it, and the program is restarted so that its late answer is not taken
+/// for the answer to the new request.
+pub struct ExternalPlayer {
+    command: String,
+    engine: Mutex<Engine>,
+}
+
+impl ExternalPlayer {
+    /// starts the program, the command is split at whitespace
+    pub fn spawn(command: &str) -> io::Result<ExternalPlayer> {
+        Ok(ExternalPlayer { command: command.to_string(), engine: Mutex::new(Engine::start(command)?) })
+    }
+
+    /// the answer without surrounding whitespace, `None` if the program is gone
+    fn ask(&self, request: &str) -> Option<String> {
+        let answers = {
+            let mut engine = self.engine.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if engine.waiting {
+                engine.stop();
+                *engine = Engine::start(&self.command).ok()?;
+            }
+            writeln!(engine.input, "{}", request).ok()?;
+            engine.input.flush().ok()?;
+            engine.waiting = true;
+            Arc::clone(&engine.answers)
+        };
+        // the engine is not locked meanwhile, so the next request can restart it
+        let answer = answers.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).recv().ok();
+        let mut engine = self.engine.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if Arc::ptr_eq(&engine.answers, &answers) {
+            engine.waiting = false;
+        }
+        answer.map(|line| line.trim().to_string())
+    }
+
+    fn request(kind: &str, table: &TableView, dice: &Dice) -> String {
+        let card = table.card();
+        let mut request = format!("{} {}", kind, write_dice(dice));
+        for (column, result) in card.columns().iter().enumerate() {
+            for &category in card.rules().categories.iter() {
+                if let Some(value) = result.get(category) {
+                    request.push_str(&format!(" {}={}", write_box(column, category), value));
+                }
+            }
+        }
+        request
+    }
+}
+
+impl Drop for ExternalPlayer {
+    fn drop(&mut self) {
+        self.engine.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).stop();
+    }
+}
+
+impl Player for ExternalPlayer {
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice {
+        let request = ExternalPlayer::request(&format!("keep {}", remaining_tosses), table, dice);
+        self.ask(&request)
+            .and_then(|answer| answer.chars().map(|c| c.to_digit(10).map(|d| d as u8)).collect())
+            .unwrap_or_else(|| dice.clone())
+    }
+
+    fn decide_entry(&self, table: &TableView, dice: &Dice) -> ColumnEntry {
+        let card = table.card();
+        let answer = self.ask(&ExternalPlayer::request("entry", table, dice))
+            .and_then(|answer| parse_box(&answer));
+        match answer {
+            Some((column, category)) => card.entry_of_category(dice, column, category),
+            None => timeout_entry(card, dice).unwrap_or((0, (Category::Chance, 0))),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.command
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("engine:{}", self.command))
+    }
+
+    fn announce(&self, table: &TableView, dice: &Dice) -> Option<(usize, Category)> {
+        let card = table.card();
+        let answer = self.ask(&ExternalPlayer::request("announce", table, dice));
+        match answer.as_deref() {
+            Some("-") if !card.must_announce() => None,
+            Some(answer) if parse_box(answer).is_some() => parse_box(answer),
+            _ => timeout_announcement(card),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::GameState;
+    use rules::RuleSet;
+    use types::Category::*;
+    use clock::{Clock, TimeControl};
+    use std::env;
+    use std::fs;
+    use std::time::Duration;
+
+    /// an engine that always keeps the fives and writes Fives
+    fn engine(name: &str) -> ExternalPlayer {
+        let script = env::temp_dir().join(format!("yahtzee-engine-{}.sh", name));
+        fs::write(&script, "while read kind rest; do\n  case $kind in\n    keep) echo 55 ;;\n    entry) echo Fives ;;\n    *) echo - ;;\n  esac\ndone\n").unwrap();
+        ExternalPlayer::spawn(&format!("sh {}", script.display())).unwrap()
+    }
+
+    #[test]
+    fn test_engine() {
+        let player = engine("test");
+        let states = vec![GameState::new(&RuleSet::yahtzee())];
+        let table = TableView::new(&states, &[], 0, 0);
+        let dice = vec![1, 1, 4, 5, 5];
+        assert_eq!(player.decide_keep(&table, 2, &dice), vec![5, 5]);
+        assert_eq!(player.decide_entry(&table, &dice), (0, (Fives, 10)));
+        assert_eq!(player.announce(&table, &dice), None);
+        assert!(player.spec().unwrap().starts_with("engine:sh "));
+    }
+
+    #[test]
+    fn test_unreadable_answer() {
+        let script = env::temp_dir().join("yahtzee-engine-garbage.sh");
+        fs::write(&script, "while read line; do echo ?; done\n").unwrap();
+        let player = ExternalPlayer::spawn(&format!("sh {}", script.display())).unwrap();
+        let states = vec![GameState::new(&RuleSet::yahtzee())];
+        let table = TableView::new(&states, &[], 0, 0);
+        let dice = vec![1, 1, 4, 5, 5];
+        assert_eq!(player.decide_keep(&table, 2, &dice), dice);
+        assert_eq!(Some(player.decide_entry(&table, &dice)), timeout_entry(table.card(), &dice));
+        assert_eq!(player.announce(&table, &dice), None);
+    }
+
+    #[test]
+    fn test_late_answer() {
+        // the first keep takes long, later ones are answered at once
+        let script = env::temp_dir().join("yahtzee-engine-slow.sh");
+        fs::write(&script, "while read kind tosses rest; do\n  if [ $tosses = 2 ]; then sleep 2; echo 55; else echo 11; fi\ndone\n").unwrap();
+        let player: Arc<dyn Player> = Arc::new(ExternalPlayer::spawn(&format!("sh {}", script.display())).unwrap());
+        let states = vec![GameState::new(&RuleSet::yahtzee())];
+        let table = TableView::new(&states, &[], 0, 0);
+        let dice = vec![1, 1, 4, 5, 5];
+        let mut clock = Clock::new(TimeControl { per_decision: Some(Duration::from_millis(200)), per_game: None }, 1);
+        assert_eq!(clock.decide(&player, &table, &dice, |player, table, dice| player.decide_keep(table, 2, dice)), None);
+        let keep = clock.decide(&player, &table, &dice, |player, table, dice| player.decide_keep(table, 1, dice));
+        assert_eq!(keep, Some(vec![1, 1]));
+    }
+}
@@ -1,86 +1,480 @@
-use dice::{Dice, DICE_NUM}; use types::{Category, Entry};
-use result::Result;
+use clock::{Clock, TimeControl};
+use dice::Dice;
+use events::{ConsoleSink, EventSink, GameEvent};
+use types::{Category, ColumnEntry};
+use rules::RuleSet;
+use scorecard::Scorecard;
+use session::{Action, Checked, GameSession};
 use player::Player;
-use rand::Rng;
-use rand::FromEntropy;
-use rand::rngs::SmallRng;
+use outcome::GameOutcome;
+use rand::{Rng, SeedableRng};
+use std::sync::Arc;
 
-const TOSS_NUM: u8 = 3;
+/// One turn of a seat as it was played.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Turn {
+    pub announcement: Option<(usize, Category)>,
+    /// the dice after every toss of the turn
+    pub tosses: Vec<Dice>,
+    /// the dice kept before every further toss, and the whole dice if the
+    /// player stopped early
+    pub keeps: Vec<Dice>,
+    pub entry: ColumnEntry,
+    /// scored zero after breaking the rules
+    pub forfeited: bool,
+    /// decisions the clock made for the player
+    pub timeouts: Vec<Timeout>,
+}
 
-pub struct GameState {
-    pub result: Result,
-    pub history: Vec<(Dice, Entry)>,
+/// A decision of a turn that was made for a player who ran out of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timeout {
+    Announce,
+    /// the keep after the given toss, from 0
+    Keep(usize),
+    Entry,
+}
+
+impl Turn {
+    /// a turn of a single toss
+    pub fn new(dice: Dice, entry: ColumnEntry) -> Turn {
+        Turn { announcement: None, tosses: vec![dice], keeps: Vec::new(), entry, forfeited: false, timeouts: Vec::new() }
+    }
+
+    /// the dice the entry was made with
+    pub fn dice(&self) -> &Dice {
+        self.tosses.last().expect("a turn has at least one toss")
+    }
 }
 
-type HistoryEntry = (Dice, Entry);
+#[derive(Clone)]
+pub struct GameState {
+    pub card: Scorecard,
+    pub history: Vec<Turn>,
+    /// tosses saved from earlier turns
+    pub roll_bank: u8,
+    /// excluded from the rest of the game after breaking the rules
+    pub disqualified: bool,
+}
 
 impl GameState {
-    pub fn new() -> GameState {
-        GameState { result: Result::new(), history: Vec::new() }
+    pub fn new(rules: &RuleSet) -> GameState {
+        GameState { card: Scorecard::new(rules), history: Vec::new(), roll_bank: 0, disqualified: false }
     }
 
-    fn write_entry(&mut self, dice: Dice, entry: Entry) {
-        self.result.add(entry);
-        self.history.push((dice, entry));
+    /// plays a recorded turn again, with its announcement and saved tosses
+    pub fn replay(&mut self, turn: Turn) {
+        let rules = self.card.rules();
+        if rules.save_rolls {
+            let toss_num = rules.toss_num + self.roll_bank;
+            self.roll_bank = toss_num.saturating_sub(turn.tosses.len() as u8);
+        }
+        self.card.set_toss(turn.tosses.len() as u8);
+        if let Some((column, category)) = turn.announcement {
+            self.card.announce(column, category);
+        }
+        self.write_turn(turn);
+    }
+
+    pub(crate) fn write_turn(&mut self, turn: Turn) {
+        self.card.add(turn.dice(), turn.entry);
+        self.history.push(turn);
+    }
+
+    #[cfg(test)]
+    pub(crate) fn write_entry(&mut self, dice: Dice, entry: ColumnEntry) {
+        self.write_turn(Turn::new(dice, entry));
+    }
+
+    /// takes back the last turn
+    pub(crate) fn undo_turn(&mut self) -> Option<Turn> {
+        let turn = self.history.pop()?;
+        let (column, (category, _)) = turn.entry;
+        self.card.remove(turn.dice(), column, category);
+        Some(turn)
+    }
+
+    /// a zero in the first box the dice could be written to
+    pub(crate) fn forfeit_entry(&mut self, dice: &Dice) -> Option<ColumnEntry> {
+        if self.card.must_announce() {
+            if let Some(&(column, category)) = self.card.announceable().first() {
+                self.card.announce(column, category);
+            }
+        }
+        self.card.possible_entries(dice).first()
+            .map(|&(column, (category, _))| (column, (category, 0)))
     }
 }
 
-pub struct Game<P: Player, R: Rng> {
-    player_states: Vec<(P, GameState)>,
-    rng: R,
+/// What happens to a player that breaks the rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViolationPolicy {
+    /// ask again up to the given number of attempts, then forfeit the turn
+    Reask(u8),
+    /// the turn scores zero
+    Forfeit,
+    /// the player takes no further turns
+    Disqualify,
 }
 
-impl<P: Player, R: Rng> Game<P, R> {
-    pub fn new(players: Vec<P>, rng: R) -> Game<P, R> {
-        let mut player_states = Vec::new();
-        for p in players {
-            let state = GameState::new();
-            player_states.push((p, state));
+impl ViolationPolicy {
+    fn attempts(&self) -> u8 {
+        match *self {
+            ViolationPolicy::Reask(attempts) => attempts.max(1),
+            _ => 1,
         }
-        Game { player_states, rng }
+    }
+}
+
+impl Default for ViolationPolicy {
+    fn default() -> ViolationPolicy {
+        ViolationPolicy::Reask(3)
+    }
+}
+
+/// rolls the dice that are not kept, in order of the game's random numbers
+pub fn toss<R: Rng>(rules: &RuleSet, rng: &mut R, keep: &Dice) -> Dice {
+    let mut new_dice = keep.clone();
+    for _ in 0..(rules.dice_num - keep.len() as u8) {
+        let r = rules.dice_faces[rng.gen_range(0, rules.dice_faces.len())];
+        new_dice.push(r);
+    }
+    new_dice.sort_unstable();
+    new_dice
+}
+
+/// Plays a `GameSession` to the end by asking the players for every decision.
+pub struct Game<R: Rng> {
+    players: Vec<Arc<dyn Player>>,
+    names: Vec<String>,
+    policy: ViolationPolicy,
+    clock: Clock,
+    session: GameSession<R>,
+    sink: Box<dyn EventSink>,
+}
+
+impl<R: Rng> Game<R> {
+    pub fn new(players: Vec<Box<dyn Player>>, rules: RuleSet, rng: R) -> Game<R> {
+        let session = GameSession::new(rules, players.len(), rng);
+        Game::from_session(players, session)
+    }
+
+    /// the players continue the session, one for every seat
+    pub fn from_session(players: Vec<Box<dyn Player>>, session: GameSession<R>) -> Game<R> {
+        let names = players.iter().map(|p| p.name().to_string()).collect();
+        let clock = Clock::new(TimeControl::default(), players.len());
+        let players = players.into_iter().map(Arc::from).collect();
+        let sink = Box::new(ConsoleSink::for_rules(session.rules()));
+        Game { players, names, policy: ViolationPolicy::default(), clock, session, sink }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        self.session.rules()
+    }
+
+    pub fn states(&self) -> &[GameState] {
+        self.session.states()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn players(&self) -> &[Arc<dyn Player>] {
+        &self.players
+    }
+
+    pub fn session(&self) -> &GameSession<R> {
+        &self.session
+    }
+
+    pub fn violation_policy(&self) -> ViolationPolicy {
+        self.policy
+    }
+
+    pub fn set_violation_policy(&mut self, policy: ViolationPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// a player out of time gets the default decision of `GameSession::time_out`
+    pub fn set_time_control(&mut self, control: TimeControl) {
+        self.clock = Clock::new(control, self.players.len());
+    }
+
+    /// continues with the time the seats used already
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = clock;
     }
 
-    pub fn run(&mut self) {
-        println!("Start Game");
-        for i in 0..Category::into_iter().count() {
-            println!("Turn {}", i);
-            for (p, state) in self.player_states.iter_mut() {
-                println!("Player {}", p.name());
-                Game::<P, R>::turn(p, state);
+    /// where the events of the game go, the console by default
+    pub fn set_event_sink(&mut self, sink: Box<dyn EventSink>) {
+        self.sink = sink;
+    }
+
+    pub fn run(&mut self) -> GameOutcome {
+        self.start();
+        while self.play_decision() {}
+        self.finish()
+    }
+
+    /// tells the event sink who plays and lets the players prepare
+    pub fn start(&mut self) {
+        self.sink.event(&GameEvent::GameStarted { players: self.names.clone() });
+        for (seat, player) in self.players.iter().enumerate() {
+            if let Some(message) = player.prepare(self.session.rules()) {
+                self.sink.event(&GameEvent::Warning { seat, message });
             }
         }
-        self.finish();
     }
 
-    fn turn(player: &P, state: &mut GameState) {
-        let mut dice = Game::<P, R>::toss(&Dice::new());
-        println!("Toss 1: {:?}", dice);
-        for i in 1..TOSS_NUM {
-            let keep = player.decide_keep(&state.result, TOSS_NUM-i, &dice);
-            println!("keep {:?}", keep);
-            dice = Game::<P, R>::toss(&keep);
-            println!("Toss {}: {:?}", i+1, dice);
+    /// asks for the pending decision and applies it, false once the game is over
+    pub fn play_decision(&mut self) -> bool {
+        self.flush_events();
+        let action = self.session.pending_action();
+        if action == Action::Finished {
+            return false
         }
-        let entry = player.decide_entry(&state.result, &dice);
-        println!("Write {} to {:?}", entry.1, entry.0);
-        state.write_entry(dice, entry);
+        let seat = self.session.seat();
+        if let Err(violation) = self.step(action) {
+            self.sink.event(&GameEvent::Violation { seat, violation, retry: false });
+            if self.policy == ViolationPolicy::Disqualify {
+                self.session.disqualify();
+            } else {
+                self.session.forfeit();
+            }
+        }
+        self.flush_events();
+        true
+    }
+
+    /// tells the event sink the scores
+    pub fn finish(&mut self) -> GameOutcome {
+        self.flush_events();
+        let scores = self.session.states().iter()
+            .map(|state| if state.disqualified { None } else { Some(state.card.value()) })
+            .collect();
+        self.sink.event(&GameEvent::GameOver { scores });
+        self.outcome()
+    }
+
+    /// ranks and scores of the game so far
+    pub fn outcome(&self) -> GameOutcome {
+        GameOutcome::new(self.session.states(), &self.names)
     }
 
-    fn toss(keep: &Dice) -> Dice {
-        let mut rng = SmallRng::from_entropy();
-        let mut new_dice = keep.clone();
-        for i in 0..(DICE_NUM - keep.len() as u8) {
-            let r = rng.gen_range(1,7);
-            new_dice.push(r);
+    fn flush_events(&mut self) {
+        for event in self.session.take_events() {
+            self.sink.event(&event);
         }
-        new_dice.sort_unstable();
-        new_dice
     }
 
-    fn finish(&self) {
-        for (p, state) in self.player_states.iter() {
-            println!("Score of Player {}: {}", p.name(), state.result.value());
+    /// asks the player for the pending decision and applies it
+    fn step(&mut self, action: Action) -> Checked<()> {
+        let policy = self.policy;
+        let session = &mut self.session;
+        let sink = &mut self.sink;
+        let clock = &mut self.clock;
+        let names = &self.names;
+        match action {
+            Action::Announce { seat } => {
+                let player = &self.players[seat];
+                ask(policy, seat, sink, || {
+                    match clock.decide(player, &session.view(names), session.dice(),
+                                       |player, table, dice| player.announce(table, dice)) {
+                        Some(announcement) => session.apply_announce(announcement),
+                        None => session.time_out(),
+                    }
+                })
+            }
+            Action::Keep { seat, remaining_tosses } => {
+                let player = &self.players[seat];
+                ask(policy, seat, sink, || {
+                    match clock.decide(player, &session.view(names), session.dice(),
+                                       move |player, table, dice| player.decide_keep(table, remaining_tosses, dice)) {
+                        Some(keep) => session.apply_keep(keep),
+                        None => session.time_out(),
+                    }
+                })
+            }
+            Action::Entry { seat } => {
+                let player = &self.players[seat];
+                ask(policy, seat, sink, || {
+                    match clock.decide(player, &session.view(names), session.dice(),
+                                       |player, table, dice| player.decide_entry(table, dice)) {
+                        Some(entry) => session.apply_entry(entry),
+                        None => session.time_out(),
+                    }
+                })
+            }
+            Action::Finished => Ok(()),
         }
     }
+}
 
+impl<R: Rng + SeedableRng> Game<R> {
+    /// A reproducible game: the same seed always gives the same dice. Only a
+    /// generator like `SeededRng` gives them on every platform, `SmallRng`
+    /// may change with the platform or the version of `rand`.
+    pub fn with_seed(players: Vec<Box<dyn Player>>, rules: RuleSet, seed: u64) -> Game<R> {
+        Game::new(players, rules, R::seed_from_u64(seed))
+    }
+}
+
+/// asks until the decision is valid or the policy gives up
+fn ask<F>(policy: ViolationPolicy, seat: usize, sink: &mut Box<dyn EventSink>, mut decide: F) -> Checked<()>
+    where F: FnMut() -> Checked<()> {
+    let mut attempts = policy.attempts();
+    loop {
+        match decide() {
+            Ok(()) => return Ok(()),
+            Err(violation) => {
+                attempts -= 1;
+                if attempts == 0 {
+                    return Err(violation)
+                }
+                sink.event(&GameEvent::Violation { seat, violation, retry: true });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+    use ai::{Difficulty, YahtzeeAI};
+    use events::SilentSink;
+    use table::TableView;
+    use rand::FromEntropy;
+    use rand::rngs::SmallRng;
+    use std::thread;
+    use std::time::Duration;
+
+    /// keeps everything and claims 50 points in Chance
+    struct Cheater;
+
+    impl Player for Cheater {
+        fn decide_keep(&self, _table: &TableView, _remaining_tosses: u8, dice: &Dice) -> Dice {
+            dice.clone()
+        }
+
+        fn decide_entry(&self, _table: &TableView, _dice: &Dice) -> ColumnEntry {
+            (0, (Chance, 50))
+        }
+
+        fn name(&self) -> &str {
+            "Cheater"
+        }
+    }
+
+    #[test]
+    fn test_forfeit() {
+        let mut game = Game::new(vec![Box::new(Cheater)], RuleSet::yahtzee(), SmallRng::from_entropy());
+        game.set_event_sink(Box::new(SilentSink));
+        game.set_violation_policy(ViolationPolicy::Forfeit);
+        game.run();
+        let state = &game.states()[0];
+        assert_eq!(state.card.open(), 0);
+        assert_eq!(state.card.value(), 0);
+        assert!(!state.disqualified);
+    }
+
+    #[test]
+    fn test_disqualify() {
+        let mut game = Game::new(vec![Box::new(Cheater)], RuleSet::yahtzee(), SmallRng::from_entropy());
+        game.set_event_sink(Box::new(SilentSink));
+        game.set_violation_policy(ViolationPolicy::Disqualify);
+        game.run();
+        let state = &game.states()[0];
+        assert!(state.disqualified);
+        assert!(state.history.is_empty());
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let rules = RuleSet::yahtzee();
+        let mut first: Game<SmallRng> = Game::with_seed(vec![Box::new(YahtzeeAI::new())], rules.clone(), 42);
+        let mut second: Game<SmallRng> = Game::with_seed(vec![Box::new(YahtzeeAI::new())], rules.clone(), 42);
+        let mut other: Game<SmallRng> = Game::with_seed(vec![Box::new(YahtzeeAI::new())], rules, 43);
+        for game in [&mut first, &mut second, &mut other].iter_mut() {
+            game.set_event_sink(Box::new(SilentSink));
+            game.run();
+        }
+        assert_eq!(first.states()[0].history, second.states()[0].history);
+        assert_eq!(first.states()[0].card.value(), second.states()[0].card.value());
+        assert_ne!(first.states()[0].history, other.states()[0].history);
+    }
+
+    #[test]
+    fn test_mixed_table() {
+        let players: Vec<Box<dyn Player>> = vec![
+            Box::new(YahtzeeAI::with_difficulty("Easy", Difficulty::Easy)),
+            Box::new(YahtzeeAI::with_difficulty("Medium", Difficulty::Medium)),
+            Box::new(Cheater),
+        ];
+        let mut game: Game<SmallRng> = Game::with_seed(players, RuleSet::yahtzee(), 3);
+        game.set_event_sink(Box::new(SilentSink));
+        let outcome = game.run();
+        assert_eq!(outcome.seats[2].rank, 3);
+        assert_eq!(outcome.seats[1].name, "Medium");
+        assert_eq!(game.players()[1].name(), "Medium");
+        assert!(game.states()[..2].iter().all(|state| state.card.open() == 0));
+        assert_eq!(game.states()[2].card.value(), 0);
+    }
+
+    /// thinks longer than any clock in the tests allows
+    struct Sleeper;
+
+    impl Player for Sleeper {
+        fn decide_keep(&self, _table: &TableView, _remaining_tosses: u8, _dice: &Dice) -> Dice {
+            thread::sleep(Duration::from_millis(200));
+            Dice::new()
+        }
+
+        fn decide_entry(&self, _table: &TableView, _dice: &Dice) -> ColumnEntry {
+            thread::sleep(Duration::from_millis(200));
+            (0, (Chance, 50))
+        }
+
+        fn name(&self) -> &str {
+            "Sleeper"
+        }
+    }
+
+    #[test]
+    fn test_time_control() {
+        let players: Vec<Box<dyn Player>> = vec![
+            Box::new(Sleeper),
+            Box::new(YahtzeeAI::with_difficulty("Easy", Difficulty::Easy)),
+        ];
+        let mut game: Game<SmallRng> = Game::with_seed(players, RuleSet::yahtzee(), 5);
+        game.set_event_sink(Box::new(SilentSink));
+        game.set_time_control(TimeControl {
+            per_decision: Some(Duration::from_millis(5)),
+            per_game: Some(Duration::from_millis(30)),
+        });
+        game.run();
+        let sleeper = &game.states()[0];
+        assert_eq!(sleeper.card.open(), 0);
+        for turn in &sleeper.history {
+            assert_eq!(turn.timeouts, vec![Timeout::Keep(0), Timeout::Entry]);
+            assert_eq!(turn.keeps, vec![turn.tosses[0].clone()]);
+        }
+        assert_eq!(game.clock().used(0), Duration::from_millis(30));
+    }
+
+    #[test]
+    fn test_toss_stream() {
+        let rules = RuleSet::yahtzee();
+        let mut first = SmallRng::seed_from_u64(7);
+        let mut second = SmallRng::seed_from_u64(7);
+        for _ in 0..100 {
+            let keep = vec![6];
+            assert_eq!(toss(&rules, &mut first, &keep), toss(&rules, &mut second, &keep));
+        }
+    }
 }
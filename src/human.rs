@@ -1,20 +1,280 @@
-use types::{Dice, Entry};
+use dice::Dice;
+use types::{Category, ColumnEntry};
 use player::Player;
+use rules::RuleSet;
+use scorecard::Scorecard;
+use table::TableView;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
 
+/// The lines of an input, read on a thread of their own so that a decision
+/// can stop waiting for one and leave it to the next decision.
+struct Lines {
+    state: Mutex<LineState>,
+    arrived: Condvar,
+}
+
+struct LineState {
+    lines: VecDeque<String>,
+    closed: bool,
+    /// counts the cancelled decisions, a read stops when it changes
+    cancelled: u64,
+}
+
+impl Lines {
+    fn start(input: Box<dyn BufRead + Send>) -> Arc<Lines> {
+        let lines = Arc::new(Lines {
+            state: Mutex::new(LineState { lines: VecDeque::new(), closed: false, cancelled: 0 }),
+            arrived: Condvar::new(),
+        });
+        let reader = Arc::clone(&lines);
+        thread::spawn(move || {
+            for line in input.lines().map_while(|line| line.ok()) {
+                reader.lock().lines.push_back(line);
+                reader.arrived.notify_all();
+            }
+            reader.lock().closed = true;
+            reader.arrived.notify_all();
+        });
+        lines
+    }
+
+    fn lock(&self) -> ::std::sync::MutexGuard<'_, LineState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// the next line, `None` at the end of the input or when cancelled
+    fn next(&self) -> Option<String> {
+        let mut state = self.lock();
+        let cancelled = state.cancelled;
+        loop {
+            if state.cancelled != cancelled {
+                return None
+            }
+            if let Some(line) = state.lines.pop_front() {
+                return Some(line)
+            }
+            if state.closed {
+                return None
+            }
+            state = self.arrived.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    fn cancel(&self) {
+        self.lock().cancelled += 1;
+        self.arrived.notify_all();
+    }
+}
+
+/// all humans share the lines of stdin
+fn stdin_lines() -> Arc<Lines> {
+    static STDIN: OnceLock<Arc<Lines>> = OnceLock::new();
+    Arc::clone(STDIN.get_or_init(|| Lines::start(Box::new(io::BufReader::new(io::stdin())))))
+}
+
+/// A player at the console, answering every decision on a line of input.
 pub struct Human {
-    name: &str,
+    name: String,
+    input: Arc<Lines>,
 }
 
 impl Human {
-    pub fn new(name: &str) {
-        Human { name }
+    pub fn new(name: &str) -> Human {
+        Human { name: String::from(name), input: stdin_lines() }
+    }
+
+    pub fn with_input(name: &str, input: Box<dyn BufRead + Send>) -> Human {
+        Human { name: String::from(name), input: Lines::start(input) }
     }
+
+    /// the next line without surrounding whitespace, `None` at the end of
+    /// the input or when the decision was cancelled
+    fn read_line(&self, prompt: &str) -> Option<String> {
+        print!("{}> ", prompt);
+        io::stdout().flush().ok();
+        self.input.next().map(|line| line.trim().to_string())
+    }
+
+    /// asks until `parse` accepts the answer
+    fn ask<T, F>(&self, prompt: &str, default: T, parse: F) -> T
+        where F: Fn(&str) -> Option<T> {
+        loop {
+            let line = match self.read_line(prompt) {
+                Some(line) => line,
+                None => return default,
+            };
+            match parse(&line) {
+                Some(answer) => return answer,
+                None => println!("Can not understand '{}'", line),
+            }
+        }
+    }
+
+    /// own card and the scores of the others
+    fn print_table(table: &TableView) {
+        println!("Turn {} of {}", table.turn() + 1, table.rules().turns());
+        for seat in table.opponents() {
+            println!("{}: {}", table.name(seat).unwrap_or("?"), table.state(seat).card.value());
+        }
+        Human::print_card(table.card());
+    }
+
+    fn print_card(card: &Scorecard) {
+        for (column, result) in card.columns().iter().enumerate() {
+            let boxes: Vec<String> = card.rules().categories.iter()
+                .map(|&cat| match result.get(cat) {
+                    Some(value) => format!("{:?} {}", cat, value),
+                    None => format!("{:?} -", cat),
+                })
+                .collect();
+            println!("Column {}: {} | total {}", column + 1, boxes.join(", "), result.value());
+        }
+    }
+}
+
+/// dice faces separated by spaces, commas or nothing at all
+fn parse_dice(line: &str) -> Option<Dice> {
+    line.chars()
+        .filter(|c| !c.is_whitespace() && *c != ',')
+        .map(|c| c.to_digit(10).map(|d| d as u8))
+        .collect()
+}
+
+fn parse_category(rules: &RuleSet, name: &str) -> Option<Category> {
+    rules.categories.iter().cloned().find(|cat| format!("{:?}", cat).eq_ignore_ascii_case(name))
 }
 
 impl Player for Human {
-    fn decide_keep(&self, round: &Round) -> Dice {
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice {
+        Human::print_table(table);
+        println!("{}: dice {:?}, {} tosses left", self.name, dice, remaining_tosses);
+        println!("Which dice to keep? ('all' to stop, empty to roll all)");
+        self.ask("keep", dice.clone(), |line| {
+            if line == "all" {
+                Some(dice.clone())
+            } else {
+                parse_dice(line)
+            }
+        })
+    }
+
+    fn decide_entry(&self, table: &TableView, dice: &Dice) -> ColumnEntry {
+        let card = table.card();
+        let entries = card.possible_entries(dice);
+        println!("{}: dice {:?}", self.name, dice);
+        for (i, &(column, (category, value))) in entries.iter().enumerate() {
+            println!("{:>2}) {:?} for {} in column {}", i + 1, category, value, column + 1);
+        }
+        let default = entries.first().cloned().unwrap_or((0, (Category::Chance, 0)));
+        self.ask("entry", default, |line| {
+            let i = line.parse::<usize>().ok()?;
+            entries.get(i.checked_sub(1)?).cloned()
+        })
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn spec(&self) -> Option<String> {
+        Some(format!("human:{}", self.name))
+    }
+
+    fn cancel(&self) {
+        self.input.cancel();
+    }
+
+    fn announce(&self, table: &TableView, dice: &Dice) -> Option<(usize, Category)> {
+        let card = table.card();
+        println!("{}: dice {:?}", self.name, dice);
+        if card.must_announce() {
+            println!("Announce a box as 'column category'");
+        } else {
+            println!("Announce a box as 'column category' or leave empty");
+        }
+        let default = if card.must_announce() { card.announceable().first().cloned() } else { None };
+        self.ask("announce", default, |line| {
+            if line.is_empty() {
+                return if card.must_announce() { None } else { Some(None) }
+            }
+            let mut words = line.split_whitespace();
+            let column = words.next()?.parse::<usize>().ok()?.checked_sub(1)?;
+            let category = parse_category(card.rules(), words.next()?)?;
+            Some(Some((column, category)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+    use game::GameState;
+    use clock::{Clock, TimeControl};
+    use std::io::{Cursor, Read};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn human(input: &str) -> Human {
+        Human::with_input("Tester", Box::new(Cursor::new(input.to_string())))
+    }
+
+    #[test]
+    fn test_parse_dice() {
+        assert_eq!(parse_dice("1 3 3"), Some(vec![1, 3, 3]));
+        assert_eq!(parse_dice("1,3,3"), Some(vec![1, 3, 3]));
+        assert_eq!(parse_dice("133"), Some(vec![1, 3, 3]));
+        assert_eq!(parse_dice(""), Some(vec![]));
+        assert_eq!(parse_dice("1 x"), None);
+    }
+
+    #[test]
+    fn test_decide() {
+        let states = vec![GameState::new(&RuleSet::yahtzee())];
+        let table = TableView::new(&states, &[], 0, 0);
+        let dice = vec![2, 3, 3, 3, 5];
+        assert_eq!(human("what\n3 3 3\n").decide_keep(&table, 2, &dice), vec![3, 3, 3]);
+        assert_eq!(human("all\n").decide_keep(&table, 2, &dice), dice);
+        let entry = human("0\n3\n").decide_entry(&table, &dice);
+        assert_eq!(entry, table.card().possible_entries(&dice)[2]);
+    }
+
+    /// input that arrives when it is sent
+    struct Typing(mpsc::Receiver<Vec<u8>>);
+
+    impl Read for Typing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let bytes = self.0.recv().unwrap_or_default();
+            buf[..bytes.len()].copy_from_slice(&bytes);
+            Ok(bytes.len())
+        }
+    }
+
+    #[test]
+    fn test_late_line() {
+        let (typing, typed) = mpsc::channel();
+        let player: Arc<dyn Player> = Arc::new(Human::with_input("Tester", Box::new(io::BufReader::new(Typing(typed)))));
+        let states = vec![GameState::new(&RuleSet::yahtzee())];
+        let table = TableView::new(&states, &[], 0, 0);
+        let dice = vec![2, 3, 3, 3, 5];
+        let mut clock = Clock::new(TimeControl { per_decision: Some(Duration::from_millis(50)), per_game: None }, 1);
+        assert_eq!(clock.decide(&player, &table, &dice, |player, table, dice| player.decide_keep(table, 2, dice)), None);
+        // typed too late for the first decision, it answers the next one
+        typing.send(b"3 3 3\n".to_vec()).unwrap();
+        clock = Clock::new(TimeControl { per_decision: Some(Duration::from_secs(10)), per_game: None }, 1);
+        let keep = clock.decide(&player, &table, &dice, |player, table, dice| player.decide_keep(table, 1, dice));
+        assert_eq!(keep, Some(vec![3, 3, 3]));
     }
 
-    fn decide_entry(&self, dices: &Dice) -> Entry {
+    #[test]
+    fn test_announce() {
+        let states = vec![GameState::new(&RuleSet::kniffel())];
+        let table = TableView::new(&states, &[], 0, 0);
+        let dice = vec![2, 3, 3, 3, 5];
+        assert_eq!(human("\n").announce(&table, &dice), None);
+        assert_eq!(human("4 threeofakind\n").announce(&table, &dice), Some((3, ThreeOfAKind)));
     }
 }
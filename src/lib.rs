@@ -0,0 +1,26 @@
+extern crate rand;
+extern crate rand_xorshift;
+
+pub mod ai;
+pub mod clock;
+pub mod dice;
+pub mod events;
+pub mod external;
+pub mod game;
+pub mod human;
+pub mod notation;
+pub mod outcome;
+pub mod player;
+pub mod probability;
+pub mod replay;
+pub mod result;
+pub mod rollout;
+pub mod rules;
+pub mod save;
+pub mod scorecard;
+pub mod session;
+pub mod solver;
+pub mod strategy;
+pub mod table;
+pub mod types;
+pub mod winning;
@@ -1,18 +1,206 @@
 extern crate rand;
+extern crate yahtzee;
 
-mod ai;
-mod dice;
-mod game;
-mod player;
-mod result;
-mod types;
-
-use ai::YahtzeeAI;
-use game::Game;
+use yahtzee::clock::TimeControl;
+use yahtzee::events::JsonLinesSink;
+use yahtzee::game::Game;
+use yahtzee::notation::GameRecord;
+use yahtzee::player::{self, Player};
+use yahtzee::replay;
+use yahtzee::rules::RuleSet;
+use yahtzee::save::SeededRng;
+use yahtzee::solver::Solver;
+use yahtzee::strategy::{self, StrategyTable};
 use rand::FromEntropy;
-use rand::rngs::SmallRng;
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+const USAGE: &str = "usage: yahtzee [--rules NAME] [--seed N] [--events FILE] [--player SPEC]...
+               [--save FILE] [--load FILE] [--record FILE] [--review FILE]
+               [--decision-time MS] [--game-time MS]
+       yahtzee solve [--rules NAME] --out FILE
+  rules:  yahtzee, triple, kniffel, yatzy, maxi, generala
+  player: human[:NAME], ai[:easy|medium|hard[:NAME]], optimal[:NAME[:TABLE]],
+          winning[:NAME[:TABLE]], rollout[:COUNT[:SEED[:BUDGET_MS]][:NAME]],
+          engine:COMMAND
+  solve:  writes the TABLE an optimal or winning player reads instead of solving
+  save:   written after every decision, --load continues it
+  record: the finished game in the text notation
+  review: replays a record and compares it with the first player
+  time:   a player out of time keeps all dice and writes the best entry";
+
+struct Options {
+    rules: RuleSet,
+    seed: Option<u64>,
+    events: Option<String>,
+    save: Option<String>,
+    load: Option<String>,
+    record: Option<String>,
+    review: Option<String>,
+    time: TimeControl,
+    players: Vec<Box<dyn Player>>,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        rules: RuleSet::yahtzee(), seed: None, events: None, save: None, load: None, record: None, review: None,
+        time: TimeControl::default(),
+        players: Vec::new(),
+    };
+    let mut specs = Vec::new();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--rules" => options.rules = RuleSet::by_name(value)
+                .ok_or_else(|| format!("Unknown rules {}", value))?,
+            "--seed" => options.seed = Some(value.parse()
+                .map_err(|_| String::from("Seed has to be a number"))?),
+            "--events" => options.events = Some(value.clone()),
+            "--save" => options.save = Some(value.clone()),
+            "--load" => options.load = Some(value.clone()),
+            "--record" => options.record = Some(value.clone()),
+            "--review" => options.review = Some(value.clone()),
+            "--decision-time" => options.time.per_decision = Some(parse_millis(value)?),
+            "--game-time" => options.time.per_game = Some(parse_millis(value)?),
+            "--player" => specs.push(value.clone()),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    if specs.is_empty() {
+        specs.push(String::from("ai"));
+    }
+    for spec in specs {
+        check_table(&spec, &options.rules)?;
+        options.players.push(player::from_spec(&spec).ok_or_else(|| format!("Unknown player {}", spec))?);
+    }
+    Ok(options)
+}
+
+/// an optimal or winning player refuses a table made for other rules
+fn check_table(spec: &str, rules: &RuleSet) -> Result<(), String> {
+    let args = spec.strip_prefix("optimal:").or_else(|| spec.strip_prefix("winning:"));
+    let path = match args.and_then(|args| args.split_once(':')) {
+        Some((_, path)) => path,
+        None => return Ok(()),
+    };
+    match StrategyTable::open(Path::new(path)) {
+        Ok(ref table) if table.fits(rules) => Ok(()),
+        Ok(_) => Err(format!("Table {} was made for other rules", path)),
+        Err(err) => Err(format!("Can not open table {}: {}", path, err)),
+    }
+}
+
+/// `solve [--rules NAME] --out FILE`
+fn solve(args: &[String]) -> Result<(), String> {
+    let mut rules = RuleSet::yahtzee();
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--rules" => rules = RuleSet::by_name(value).ok_or_else(|| format!("Unknown rules {}", value))?,
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    let out = out.ok_or_else(|| String::from("Missing --out"))?;
+    let mut solver = Solver::new(&rules).ok_or_else(|| String::from("These rules can not be solved"))?;
+    solver.solve();
+    solver.solve_variances();
+    strategy::write(&solver, Path::new(&out)).map_err(|err| format!("Can not write {}: {}", out, err))?;
+    println!("Expected score {:.4}", solver.expected_score());
+    Ok(())
+}
+
+fn parse_millis(value: &str) -> Result<Duration, String> {
+    value.parse().map(Duration::from_millis).map_err(|_| String::from("Time has to be a number of milliseconds"))
+}
 
 fn main() {
-    let mut game = Game::new(vec![YahtzeeAI::new()], SmallRng::from_entropy());
-    game.run();
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("solve") {
+        if let Err(err) = solve(&args[1..]) {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        }
+        return
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, USAGE);
+            process::exit(1);
+        }
+    };
+    if let Some(path) = options.review {
+        review(&path, &*options.players[0]);
+        return
+    }
+    let mut game = match (options.load, options.seed) {
+        (Some(path), _) => match Game::load_from(Path::new(&path)) {
+            Ok(game) => game,
+            Err(err) => {
+                eprintln!("Can not load {}: {}", path, err);
+                process::exit(1);
+            }
+        },
+        (None, Some(seed)) => Game::with_seed(options.players, options.rules, seed),
+        (None, None) => Game::new(options.players, options.rules, SeededRng::from_entropy()),
+    };
+    if !options.time.is_unlimited() {
+        game.set_time_control(options.time);
+    }
+    if let Some(path) = options.events {
+        match File::create(&path) {
+            Ok(file) => game.set_event_sink(Box::new(JsonLinesSink::new(BufWriter::new(file)))),
+            Err(err) => {
+                eprintln!("Can not create {}: {}", path, err);
+                process::exit(1);
+            }
+        }
+    }
+    game.start();
+    while game.play_decision() {
+        if let Some(ref path) = options.save {
+            if let Err(err) = game.save_to(Path::new(path)) {
+                eprintln!("Can not save {}: {}", path, err);
+                process::exit(1);
+            }
+        }
+    }
+    let outcome = game.finish();
+    if let Some(path) = options.record {
+        if let Err(err) = fs::write(&path, GameRecord::of(&game).write()) {
+            eprintln!("Can not write {}: {}", path, err);
+            process::exit(1);
+        }
+    }
+    if outcome.seats.len() > 1 {
+        for seat in outcome.ranking() {
+            println!("{}. {} ({})", seat.rank, seat.name, seat.value);
+        }
+    }
+}
+
+fn review(path: &str, reviewer: &dyn Player) {
+    let record = match fs::read_to_string(path).map_err(|err| err.to_string())
+        .and_then(|text| GameRecord::parse(&text).map_err(|err| err.to_string())) {
+        Ok(record) => record,
+        Err(err) => {
+            eprintln!("Can not read {}: {}", path, err);
+            process::exit(1);
+        }
+    };
+    match replay::review(&record, reviewer) {
+        Ok(review) => print!("{}", review.write()),
+        Err(err) => {
+            eprintln!("Can not replay {}: {}", path, err);
+            process::exit(1);
+        }
+    }
 }
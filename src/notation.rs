@@ -0,0 +1,482 @@
+//! A text record of a whole game, similar to PGN for chess:
+//!
+//! ```text
+//! [Rules "yahtzee"]
+//! [Seats "2"]
+//! [Seat1 "KI"]
+//! [Seat2 "Bob"]
+//! [Result "231 -"]
+//!
+//! 1.1 11455 k55 12355 k555 23555 Fives=15
+//! 1.2 22346 k2346 12346 k12346 LargeStraght=40
+//! 2.1 !Chance@4 13456 k1345 13345 xChance@4=0
+//! ```
+//!
+//! Every turn is one line numbered `turn.seat`, both from 1. It holds the
+//! announced box, the dice of every toss as digits, the kept dice after
+//! `k` and the entry as `box=value`. A box is the category followed by
+//! `@column` for all but the first column, `x` marks a forfeited turn.
+//! A `t` before an announcement, a keep or an entry marks a decision the
+//! clock made for a player out of time, `!t-` one without announcement.
+//! The result has the score of every seat, `-` for disqualified ones.
+
+use dice::{Dice, is_subset};
+use game::{Game, GameState, Timeout, Turn};
+use rules::{Order, RuleSet};
+use save::{self, Fields};
+use session::{check_announcement, check_entry, check_keep};
+use types::Category;
+use rand::Rng;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// Where and why a record could not be read.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NotationError {
+    /// from 1, 0 if the whole record is affected
+    pub line: usize,
+    pub message: String,
+}
+
+impl NotationError {
+    fn new<S: Into<String>>(line: usize, message: S) -> NotationError {
+        NotationError { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for NotationError {}
+
+pub type Parsed<T> = ::std::result::Result<T, NotationError>;
+
+/// The rules, the seats and every turn that was played.
+#[derive(Clone)]
+pub struct GameRecord {
+    pub rules: RuleSet,
+    pub names: Vec<String>,
+    pub states: Vec<GameState>,
+}
+
+impl GameRecord {
+    pub fn of<R: Rng>(game: &Game<R>) -> GameRecord {
+        GameRecord {
+            rules: game.rules().clone(),
+            names: game.names().to_vec(),
+            states: game.states().to_vec(),
+        }
+    }
+
+    pub fn write(&self) -> String {
+        let mut out = String::new();
+        match self.rules.name() {
+            Some(name) => out.push_str(&tag("Rules", name)),
+            None => {
+                out.push_str(&tag("Rules", "custom"));
+                for (key, value) in save::rule_fields(&self.rules) {
+                    out.push_str(&tag(&format!("rules.{}", key), &value));
+                }
+            }
+        }
+        out.push_str(&tag("Seats", &self.names.len().to_string()));
+        for (seat, name) in self.names.iter().enumerate() {
+            out.push_str(&tag(&format!("Seat{}", seat + 1), name));
+        }
+        out.push_str(&tag("Result", &result(&self.states)));
+        out.push('\n');
+        let turns = self.states.iter().map(|state| state.history.len()).max().unwrap_or(0);
+        for turn in 0..turns {
+            for (seat, state) in self.states.iter().enumerate() {
+                if let Some(played) = state.history.get(turn) {
+                    out.push_str(&format!("{}.{} {}\n", turn + 1, seat + 1, write_turn(played)));
+                }
+            }
+        }
+        out
+    }
+
+    /// reads a record and checks every turn against the rules
+    pub fn parse(text: &str) -> Parsed<GameRecord> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let mut tags = HashMap::new();
+        for (number, line) in lines.by_ref() {
+            if line.is_empty() {
+                break
+            }
+            let (key, value) = parse_tag(line).ok_or_else(|| NotationError::new(number, "invalid tag"))?;
+            if tags.insert(key.clone(), value).is_some() {
+                return Err(NotationError::new(number, format!("tag {} twice", key)))
+            }
+        }
+        let header = |key: &str| tags.get(key).cloned()
+            .ok_or_else(|| NotationError::new(0, format!("missing tag {}", key)));
+        let rules = match header("Rules")?.as_str() {
+            "custom" => {
+                let fields = tags.iter()
+                    .filter(|(key, _)| key.starts_with("rules."))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect();
+                save::read_rules(&Fields::new(fields))
+                    .map_err(|err| NotationError::new(0, err.to_string()))?
+            }
+            name => RuleSet::by_name(name).ok_or_else(|| NotationError::new(0, format!("unknown rules {}", name)))?,
+        };
+        let seats: usize = header("Seats")?.parse()
+            .map_err(|_| NotationError::new(0, "invalid number of seats"))?;
+        let names = (1..=seats).map(|seat| header(&format!("Seat{}", seat))).collect::<Parsed<Vec<_>>>()?;
+        let known = |key: &String| key == "Rules" || key == "Seats" || key == "Result" || key.starts_with("rules.")
+            || (key.starts_with("Seat") && key[4..].parse().is_ok_and(|seat: usize| seat >= 1 && seat <= seats));
+        if let Some(key) = tags.keys().find(|key| !known(key)) {
+            return Err(NotationError::new(0, format!("unknown tag {}", key)))
+        }
+
+        let mut states: Vec<GameState> = (0..seats).map(|_| GameState::new(&rules)).collect();
+        let mut last = None;
+        for (number, line) in lines {
+            let error = |message: &str| NotationError::new(number, message);
+            if states.iter().any(|state| state.card.has_won()) {
+                return Err(error("turn after the game was won"))
+            }
+            let mut parts = line.splitn(2, ' ');
+            let (turn, seat) = parse_number(parts.next().unwrap_or(""))
+                .filter(|&(_, seat)| seat < seats)
+                .ok_or_else(|| error("invalid turn number"))?;
+            if last.is_some_and(|last| (turn, seat) <= last) || states[seat].history.len() != turn {
+                return Err(error("turn out of order"))
+            }
+            last = Some((turn, seat));
+            let played = parts.next().and_then(parse_turn).ok_or_else(|| error("invalid turn"))?;
+            check_turn(&states[seat], &played).map_err(|message| error(&message))?;
+            states[seat].replay(played);
+        }
+
+        let results = header("Result")?;
+        let results: Vec<&str> = results.split(' ').collect();
+        if results.len() != seats {
+            return Err(NotationError::new(0, "result of every seat needed"))
+        }
+        for (state, result) in states.iter_mut().zip(results) {
+            if result == "-" {
+                state.disqualified = true;
+            } else if result != state.card.value().to_string() {
+                return Err(NotationError::new(0, format!("result {} does not match the turns", result)))
+            }
+        }
+        Ok(GameRecord { rules, names, states })
+    }
+}
+
+/// a turn keeps to the rules and the card of the seat
+pub(crate) fn check_turn(state: &GameState, turn: &Turn) -> ::std::result::Result<(), String> {
+    let rules = state.card.rules();
+    let allowed = rules.toss_num + state.roll_bank;
+    if turn.tosses.len() > allowed as usize {
+        return Err(format!("more than {} tosses", allowed))
+    }
+    for dice in &turn.tosses {
+        if dice.len() != rules.dice_num as usize || dice.iter().any(|die| !rules.dice_faces.contains(die)) {
+            return Err(format!("invalid toss {:?}", dice))
+        }
+    }
+    if turn.keeps.len() + 1 < turn.tosses.len() || turn.keeps.len() > turn.tosses.len() {
+        return Err(String::from("a keep for every toss but the last needed"))
+    }
+    if turn.tosses.len() < allowed as usize && turn.keeps.len() < turn.tosses.len() && !turn.forfeited {
+        return Err(String::from("only keeping all dice ends the tossing early"))
+    }
+    for (i, keep) in turn.keeps.iter().enumerate() {
+        check_keep(&turn.tosses[i], keep).map_err(|violation| violation.to_string())?;
+        match turn.tosses.get(i + 1) {
+            Some(next) if !is_subset(keep, next) => return Err(format!("toss {:?} lost the kept dice", next)),
+            None if keep != &turn.tosses[i] => return Err(String::from("only keeping all dice ends the tossing")),
+            _ => {}
+        }
+    }
+    let mut card = state.card.clone();
+    card.set_toss(turn.tosses.len() as u8);
+    if rules.columns.iter().any(|column| column.order == Order::Announce) {
+        check_announcement(&card, turn.announcement).map_err(|violation| violation.to_string())?;
+    } else if turn.announcement.is_some() {
+        return Err(String::from("no column to announce in"))
+    }
+    if let Some((column, category)) = turn.announcement {
+        card.announce(column, category);
+    }
+    let (column, (category, value)) = turn.entry;
+    if turn.forfeited {
+        if value != 0 || !card.can_write(turn.dice(), column, category) {
+            return Err(String::from("a forfeited turn scores 0 in an open box"))
+        }
+        Ok(())
+    } else {
+        check_entry(&card, turn.dice(), turn.entry).map_err(|violation| violation.to_string())
+    }
+}
+
+fn tag(key: &str, value: &str) -> String {
+    format!("[{} \"{}\"]\n", key, value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_at(inner.find(' ')?);
+    let value = value[1..].strip_prefix('"')?.strip_suffix('"')?;
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next()?),
+            '"' => return None,
+            c => unescaped.push(c),
+        }
+    }
+    if key.is_empty() || key.contains('"') {
+        return None
+    }
+    Some((key.to_string(), unescaped))
+}
+
+fn result(states: &[GameState]) -> String {
+    states.iter()
+        .map(|state| if state.disqualified { String::from("-") } else { state.card.value().to_string() })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `turn.seat` from 1 to indices from 0
+fn parse_number(text: &str) -> Option<(usize, usize)> {
+    let mut parts = text.splitn(2, '.');
+    let turn: usize = parts.next()?.parse().ok()?;
+    let seat: usize = parts.next()?.parse().ok()?;
+    Some((turn.checked_sub(1)?, seat.checked_sub(1)?))
+}
+
+pub(crate) fn write_dice(dice: &Dice) -> String {
+    dice.iter().map(|die| die.to_string()).collect()
+}
+
+fn parse_dice(text: &str) -> Option<Dice> {
+    text.chars()
+        .map(|c| c.to_digit(10).filter(|&d| d > 0).map(|d| d as u8))
+        .collect()
+}
+
+/// `Category` or `Category@column` with the column from 1
+pub(crate) fn write_box(column: usize, category: Category) -> String {
+    if column == 0 {
+        format!("{:?}", category)
+    } else {
+        format!("{:?}@{}", category, column + 1)
+    }
+}
+
+pub(crate) fn parse_box(text: &str) -> Option<(usize, Category)> {
+    let mut parts = text.splitn(2, '@');
+    let category = Category::by_name(parts.next()?)?;
+    let column = match parts.next() {
+        Some(column) => column.parse::<usize>().ok().filter(|&column| column > 1)? - 1,
+        None => 0,
+    };
+    Some((column, category))
+}
+
+/// the tosses and keeps of a turn, `11455 k55 12355`, a `t` marks the keeps
+/// made by the clock
+pub(crate) fn write_moves(tosses: &[Dice], keeps: &[Dice], timeouts: &[Timeout]) -> String {
+    let mut moves = Vec::new();
+    for (i, dice) in tosses.iter().enumerate() {
+        moves.push(write_dice(dice));
+        if let Some(keep) = keeps.get(i) {
+            let timeout = if timeouts.contains(&Timeout::Keep(i)) { "t" } else { "" };
+            moves.push(format!("{}k{}", timeout, write_dice(keep)));
+        }
+    }
+    moves.join(" ")
+}
+
+/// the tosses, the keeps and the keeps that timed out
+pub(crate) fn parse_moves(text: &str) -> Option<(Vec<Dice>, Vec<Dice>, Vec<Timeout>)> {
+    let mut tosses = Vec::new();
+    let mut keeps = Vec::new();
+    let mut timeouts = Vec::new();
+    for word in text.split(' ') {
+        let (word, timeout) = match word.strip_prefix('t') {
+            Some(word) => (word, true),
+            None => (word, false),
+        };
+        if let Some(keep) = word.strip_prefix('k') {
+            if keeps.len() + 1 != tosses.len() {
+                return None
+            }
+            if timeout {
+                timeouts.push(Timeout::Keep(keeps.len()));
+            }
+            keeps.push(parse_dice(keep)?);
+        } else {
+            if keeps.len() != tosses.len() || word.is_empty() || timeout {
+                return None
+            }
+            tosses.push(parse_dice(word)?);
+        }
+    }
+    if tosses.is_empty() {
+        return None
+    }
+    Some((tosses, keeps, timeouts))
+}
+
+pub(crate) fn write_turn(turn: &Turn) -> String {
+    let mut text = String::new();
+    let timed_out = |timeout| if turn.timeouts.contains(&timeout) { "t" } else { "" };
+    match turn.announcement {
+        Some((column, category)) =>
+            text.push_str(&format!("!{}{} ", timed_out(Timeout::Announce), write_box(column, category))),
+        // the clock made no announcement
+        None if turn.timeouts.contains(&Timeout::Announce) => text.push_str("!t- "),
+        None => {}
+    }
+    text.push_str(&write_moves(&turn.tosses, &turn.keeps, &turn.timeouts));
+    let (column, (category, value)) = turn.entry;
+    let forfeited = if turn.forfeited { "x" } else { "" };
+    text.push_str(&format!(" {}{}{}={}", forfeited, timed_out(Timeout::Entry), write_box(column, category), value));
+    text
+}
+
+pub(crate) fn parse_turn(text: &str) -> Option<Turn> {
+    let mut timeouts = Vec::new();
+    let (text, announcement) = match text.strip_prefix('!') {
+        Some(rest) => {
+            let (announced, rest) = rest.split_at(rest.find(' ')?);
+            let announced = match announced.strip_prefix('t') {
+                Some(announced) => {
+                    timeouts.push(Timeout::Announce);
+                    announced
+                }
+                None => announced,
+            };
+            let announcement = match announced {
+                "-" if !timeouts.is_empty() => None,
+                _ => Some(parse_box(announced)?),
+            };
+            (&rest[1..], announcement)
+        }
+        None => (text, None),
+    };
+    let (moves, entry) = text.split_at(text.rfind(' ')?);
+    let (tosses, keeps, keep_timeouts) = parse_moves(moves)?;
+    timeouts.extend(keep_timeouts);
+    let entry = &entry[1..];
+    let (entry, forfeited) = match entry.strip_prefix('x') {
+        Some(entry) => (entry, true),
+        None => (entry, false),
+    };
+    let entry = match entry.strip_prefix('t') {
+        Some(entry) => {
+            timeouts.push(Timeout::Entry);
+            entry
+        }
+        None => entry,
+    };
+    let mut parts = entry.splitn(2, '=');
+    let (column, category) = parse_box(parts.next()?)?;
+    let value = parts.next()?;
+    if value.starts_with('+') {
+        return None
+    }
+    let value = value.parse().ok()?;
+    Some(Turn { announcement, tosses, keeps, entry: (column, (category, value)), forfeited, timeouts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai::{Difficulty, YahtzeeAI};
+    use events::SilentSink;
+    use player::Player;
+    use types::Category::*;
+    use rand::rngs::SmallRng;
+
+    fn play(rules: RuleSet, seed: u64) -> GameRecord {
+        let players: Vec<Box<dyn Player>> = vec![
+            Box::new(YahtzeeAI::with_difficulty("A", Difficulty::Easy)),
+            Box::new(YahtzeeAI::with_difficulty("B \"the\" Bot", Difficulty::Easy)),
+        ];
+        let mut game: Game<SmallRng> = Game::with_seed(players, rules, seed);
+        game.set_event_sink(Box::new(SilentSink));
+        game.run();
+        GameRecord::of(&game)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut custom = RuleSet::yahtzee();
+        custom.toss_num = 4;
+        for rules in [RuleSet::yahtzee(), RuleSet::kniffel(), RuleSet::maxi_yatzy(), custom].iter() {
+            let record = play(rules.clone(), 9);
+            let text = record.write();
+            let parsed = GameRecord::parse(&text).unwrap();
+            assert_eq!(&parsed.rules, rules);
+            assert_eq!(parsed.names, record.names);
+            for (parsed, played) in parsed.states.iter().zip(record.states.iter()) {
+                assert_eq!(parsed.history, played.history);
+                assert_eq!(parsed.card.value(), played.card.value());
+            }
+            assert_eq!(parsed.write(), text);
+        }
+    }
+
+    #[test]
+    fn test_turn() {
+        let turn = Turn {
+            announcement: Some((3, Chance)),
+            tosses: vec![vec![1, 1, 4, 5, 5], vec![1, 2, 3, 5, 5]],
+            keeps: vec![vec![5, 5], vec![1, 2, 3, 5, 5]],
+            entry: (3, (Chance, 16)),
+            forfeited: false,
+            timeouts: Vec::new(),
+        };
+        assert_eq!(write_turn(&turn), "!Chance@4 11455 k55 12355 k12355 Chance@4=16");
+        assert_eq!(parse_turn("!Chance@4 11455 k55 12355 k12355 Chance@4=16"), Some(turn));
+        let forfeited = parse_turn("11455 k xOnes=0").unwrap();
+        assert!(forfeited.forfeited);
+        assert_eq!(forfeited.keeps, vec![vec![]]);
+        assert_eq!(parse_turn("11455 k55 k55 Ones=2"), None);
+        assert_eq!(parse_turn("11455 Ones@1=2"), None);
+        assert_eq!(parse_turn("11455 Ones=+2"), None);
+        assert_eq!(parse_turn("11405 Ones=2"), None);
+        let timed_out = parse_turn("!t- 11455 tk11455 tOnes=2").unwrap();
+        assert_eq!(timed_out.timeouts, vec![Timeout::Announce, Timeout::Keep(0), Timeout::Entry]);
+        assert_eq!(timed_out.announcement, None);
+        assert_eq!(write_turn(&timed_out), "!t- 11455 tk11455 tOnes=2");
+        assert_eq!(parse_turn("!- 11455 Ones=2"), None);
+        assert_eq!(parse_turn("t11455 Ones=2"), None);
+    }
+
+    #[test]
+    fn test_strict() {
+        let text = "[Rules \"yahtzee\"]\n[Seats \"1\"]\n[Seat1 \"A\"]\n[Result \"2\"]\n\n1.1 11455 k11 11236 k11 11235 Ones=2\n";
+        assert!(GameRecord::parse(text).is_ok());
+        assert!(GameRecord::parse(&text.replace("k11 11235 Ones", "k11236 Ones")).is_ok());
+        let forfeited = text.replace(" k11 11235 Ones=2", " xOnes=0").replace("\"2\"", "\"0\"");
+        assert!(GameRecord::parse(&forfeited).is_ok());
+        let errors = [
+            (text.replace("k11 11236", "k11 12236"), 6),
+            (text.replace("Ones=2", "Ones=3"), 6),
+            (text.replace("11235 Ones", "11235 k11 11111 Ones"), 6),
+            (text.replace(" k11 11235 Ones", " Ones"), 6),
+            (text.replace("1.1", "2.1"), 6),
+            (text.replace("[Result \"2\"]", "[Result \"3\"]"), 0),
+            (text.replace("[Seats", "[Players"), 0),
+            (text.replace("[Seat1 \"A\"]", "[Seat1 \"A\"]\n[Seat1 \"B\"]"), 4),
+        ];
+        for (text, line) in errors.iter() {
+            let err = GameRecord::parse(text).err().expect(text);
+            assert_eq!(err.line, *line, "{}", err);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use game::{GameState, Turn};
+use result::Result;
+use types::{Category, Value};
+
+/// Score of one column at the end of the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnOutcome {
+    pub weight: u32,
+    /// every box of the rules in order, `None` if it stayed open
+    pub boxes: Vec<(Category, Option<Value>)>,
+    pub upper: u32,
+    pub lower: u32,
+    pub bonus: bool,
+    pub yahtzee_bonus: u8,
+    /// unweighted total of the column
+    pub value: u32,
+}
+
+impl ColumnOutcome {
+    fn new(result: &Result, weight: u32) -> ColumnOutcome {
+        ColumnOutcome {
+            weight,
+            boxes: result.rules().categories.iter().map(|&cat| (cat, result.get(cat))).collect(),
+            upper: result.value_upper(),
+            lower: result.value_lower(),
+            bonus: result.has_bonus(),
+            yahtzee_bonus: result.yahtzee_bonus(),
+            value: result.value(),
+        }
+    }
+}
+
+/// How one seat finished.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeatOutcome {
+    pub seat: usize,
+    pub name: String,
+    /// 1 for the winners, tied seats share a rank
+    pub rank: usize,
+    pub value: u32,
+    pub disqualified: bool,
+    /// ended the game with a served Generala
+    pub won_instantly: bool,
+    pub columns: Vec<ColumnOutcome>,
+    pub history: Vec<Turn>,
+}
+
+impl SeatOutcome {
+    /// order of the ranking: instant wins first, then by score, disqualified last
+    fn standing(&self) -> (bool, bool, u32) {
+        (!self.disqualified, self.won_instantly, self.value)
+    }
+}
+
+/// Result of a whole game, in seat order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameOutcome {
+    pub seats: Vec<SeatOutcome>,
+}
+
+impl GameOutcome {
+    pub fn new(states: &[GameState], names: &[String]) -> GameOutcome {
+        let mut seats: Vec<SeatOutcome> = states.iter().enumerate().map(|(seat, state)| {
+            let card = &state.card;
+            SeatOutcome {
+                seat,
+                name: names.get(seat).cloned().unwrap_or_default(),
+                rank: 0,
+                value: card.value(),
+                disqualified: state.disqualified,
+                won_instantly: card.has_won(),
+                columns: card.columns().iter().enumerate()
+                    .map(|(column, result)| ColumnOutcome::new(result, card.weight(column)))
+                    .collect(),
+                history: state.history.clone(),
+            }
+        }).collect();
+        let standings: Vec<_> = seats.iter().map(|seat| seat.standing()).collect();
+        for seat in seats.iter_mut() {
+            let standing = seat.standing();
+            seat.rank = 1 + standings.iter().filter(|&&other| other > standing).count();
+        }
+        GameOutcome { seats }
+    }
+
+    /// seats from the first to the last rank, ties in seat order
+    pub fn ranking(&self) -> Vec<&SeatOutcome> {
+        let mut ranking: Vec<&SeatOutcome> = self.seats.iter().collect();
+        ranking.sort_by_key(|seat| seat.rank);
+        ranking
+    }
+
+    pub fn winners(&self) -> Vec<usize> {
+        self.seats.iter().filter(|seat| seat.rank == 1).map(|seat| seat.seat).collect()
+    }
+
+    /// more than one seat shares the first rank
+    pub fn is_tie(&self) -> bool {
+        self.winners().len() > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rules::RuleSet;
+    use types::Category::*;
+
+    fn names(num: usize) -> Vec<String> {
+        (0..num).map(|seat| format!("P{}", seat)).collect()
+    }
+
+    #[test]
+    fn test_ranks() {
+        let rules = RuleSet::yahtzee();
+        let mut states: Vec<GameState> = (0..4).map(|_| GameState::new(&rules)).collect();
+        states[0].write_entry(vec![1, 2, 3, 4, 5], (0, (LargeStraght, 40)));
+        states[1].write_entry(vec![2, 2, 2, 3, 3], (0, (FullHouse, 25)));
+        states[2].write_entry(vec![1, 2, 3, 4, 5], (0, (LargeStraght, 40)));
+        states[3].write_entry(vec![6, 6, 6, 6, 6], (0, (Yahtzee, 50)));
+        states[3].disqualified = true;
+        let outcome = GameOutcome::new(&states, &names(4));
+        let ranks: Vec<usize> = outcome.seats.iter().map(|seat| seat.rank).collect();
+        assert_eq!(ranks, vec![1, 3, 1, 4]);
+        assert_eq!(outcome.winners(), vec![0, 2]);
+        assert!(outcome.is_tie());
+        let ranking: Vec<usize> = outcome.ranking().iter().map(|seat| seat.seat).collect();
+        assert_eq!(ranking, vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_breakdown() {
+        let rules = RuleSet::yahtzee();
+        let mut state = GameState::new(&rules);
+        state.write_entry(vec![6, 6, 6, 6, 6], (0, (Sixes, 30)));
+        state.write_entry(vec![5, 5, 5, 5, 1], (0, (Fives, 20)));
+        state.write_entry(vec![4, 4, 4, 4, 1], (0, (Fours, 16)));
+        state.write_entry(vec![3, 3, 3, 1, 1], (0, (Chance, 11)));
+        let outcome = GameOutcome::new(&[state], &names(1));
+        let column = &outcome.seats[0].columns[0];
+        assert_eq!(column.upper, 66);
+        assert_eq!(column.lower, 11);
+        assert!(column.bonus);
+        assert_eq!(column.value, 66 + 35 + 11);
+        assert_eq!(column.boxes[5], (Sixes, Some(30)));
+        assert_eq!(column.boxes[0], (Ones, None));
+        assert_eq!(outcome.seats[0].history.len(), 4);
+        assert_eq!(outcome.seats[0].name, "P0");
+    }
+
+    #[test]
+    fn test_instant_win_ranks_first() {
+        let rules = RuleSet::generala();
+        let mut states: Vec<GameState> = (0..2).map(|_| GameState::new(&rules)).collect();
+        states[0].write_entry(vec![6, 6, 6, 6, 1], (0, (Sixes, 24)));
+        states[0].write_entry(vec![3, 3, 3, 3, 3], (0, (Yahtzee, 50)));
+        states[1].card.set_toss(1);
+        states[1].write_entry(vec![2, 2, 2, 2, 2], (0, (Yahtzee, 50)));
+        let outcome = GameOutcome::new(&states, &names(2));
+        assert!(outcome.seats[1].won_instantly);
+        assert_eq!(outcome.winners(), vec![1]);
+    }
+}
@@ -1,9 +1,140 @@
 use dice::Dice;
-use types::Entry;
-use result::Result;
+use types::{Category, ColumnEntry};
+use table::TableView;
+use rules::RuleSet;
+use ai::{Difficulty, YahtzeeAI};
+use external::ExternalPlayer;
+use human::Human;
+use rollout::{RolloutAI, Rollouts};
+use solver::OptimalAI;
+use strategy::StrategyTable;
+use winning::WinningAI;
+use std::path::Path;
+use std::time::Duration;
 
-pub trait Player {
-    fn decide_keep(&self, result: &Result, remaining_tosses: u8, dice: &Dice) -> Dice;
-    fn decide_entry(&self, result: &Result, dices: &Dice) -> Entry;
+/// Someone deciding for a seat. Players are `Send` and `Sync` as a game with
+/// a clock asks them on a thread of their own.
+pub trait Player: Send + Sync {
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice;
+    fn decide_entry(&self, table: &TableView, dices: &Dice) -> ColumnEntry;
     fn name(&self) -> &str;
+
+    /// how `from_spec` makes this player again, `None` if it can not
+    fn spec(&self) -> Option<String> {
+        None
+    }
+
+    /// Called when a game starts, before the clock runs. A player that
+    /// needs long to get ready for the rules, like one that solves them,
+    /// does it here and not in its first decision. A warning for the event
+    /// sink if it could not get ready as asked.
+    fn prepare(&self, _rules: &RuleSet) -> Option<String> {
+        None
+    }
+
+    /// Called when the clock stopped waiting for a decision. A player that
+    /// blocks, like one waiting for input, gives up the decision so that it
+    /// does not take what belongs to the next one.
+    fn cancel(&self) {}
+
+    /// Called after the first toss when the rules have an announce column.
+    /// The announced box is the only one the turn may be written to.
+    fn announce(&self, table: &TableView, _dice: &Dice) -> Option<(usize, Category)> {
+        let card = table.card();
+        if card.must_announce() {
+            card.announceable().first().cloned()
+        } else {
+            None
+        }
+    }
+}
+
+/// A player from a spec like `human:Alice`, `ai`, `ai:easy`, `ai:easy:Bob`,
+/// `optimal[:NAME[:TABLE]]`, `winning[:NAME[:TABLE]]`, `rollout[:COUNT[:SEED[:BUDGET_MS]][:NAME]]`
+/// or `engine:COMMAND` for another program, `None` if it can not be started.
+pub fn from_spec(spec: &str) -> Option<Box<dyn Player>> {
+    let (kind, arg) = match spec.find(':') {
+        Some(i) => (&spec[..i], Some(&spec[i + 1..])),
+        None => (spec, None),
+    };
+    match kind {
+        "human" => Some(Box::new(Human::new(arg.unwrap_or("Human")))),
+        "ai" => {
+            let mut args = arg.unwrap_or("hard").splitn(2, ':');
+            let level = args.next()?;
+            let difficulty = Difficulty::by_name(level)?;
+            let name = args.next().map_or_else(|| format!("KI ({})", level), String::from);
+            Some(Box::new(YahtzeeAI::with_difficulty(&name, difficulty)))
+        }
+        "optimal" => {
+            let mut args = arg.unwrap_or("Optimal").splitn(2, ':');
+            let name = args.next()?;
+            match args.next() {
+                Some(path) => Some(Box::new(OptimalAI::with_table(name, StrategyTable::open(Path::new(path)).ok()?))),
+                None => Some(Box::new(OptimalAI::new(name))),
+            }
+        }
+        "winning" => {
+            let mut args = arg.unwrap_or("Winning").splitn(2, ':');
+            let name = args.next()?;
+            match args.next() {
+                Some(path) => Some(Box::new(WinningAI::with_table(name, StrategyTable::open(Path::new(path)).ok()?))),
+                None => Some(Box::new(WinningAI::new(name))),
+            }
+        }
+        "rollout" => {
+            let args: Vec<&str> = arg.unwrap_or("").split(':').collect();
+            let count = match args[0] {
+                "" => Rollouts::default().count,
+                count => count.parse().ok()?,
+            };
+            // the numbers after the count are the seed and the budget
+            let numbers: Vec<u64> = args[1..].iter().map_while(|arg| arg.parse().ok()).take(2).collect();
+            let name = match args[1 + numbers.len()..].join(":") {
+                ref name if name.is_empty() => String::from("Rollout"),
+                name => name,
+            };
+            let rollouts = Rollouts {
+                count,
+                seed: numbers.first().cloned().unwrap_or(Rollouts::default().seed),
+                budget: numbers.get(1).map(|&millis| Duration::from_millis(millis)),
+                ..Rollouts::default()
+            };
+            Some(Box::new(RolloutAI::with_rollouts(&name, rollouts)))
+        }
+        "engine" => Some(Box::new(ExternalPlayer::spawn(arg?).ok()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_spec() {
+        assert_eq!(from_spec("human:Alice").unwrap().name(), "Alice");
+        assert_eq!(from_spec("ai:easy").unwrap().name(), "KI (easy)");
+        assert_eq!(from_spec("ai").unwrap().name(), "KI (hard)");
+        assert_eq!(from_spec("ai:easy:Bob").unwrap().name(), "Bob");
+        assert!(from_spec("ai:impossible").is_none());
+        assert_eq!(from_spec("optimal").unwrap().name(), "Optimal");
+        assert!(from_spec("optimal:Opti:/no/such/table").is_none());
+        assert_eq!(from_spec("winning").unwrap().name(), "Winning");
+        assert_eq!(from_spec("rollout").unwrap().spec().as_deref(), Some("rollout:100:Rollout"));
+        assert!(from_spec("rollout:many").is_none());
+        let rollout = RolloutAI::with_rollouts("7", Rollouts { count: 10, seed: 3, ..Rollouts::default() });
+        assert_eq!(rollout.spec(), None);
+        assert_eq!(from_spec("rollout:10:3:7").unwrap().name(), "Rollout");
+        assert!(from_spec("robot").is_none());
+        assert!(from_spec("engine:/no/such/engine").is_none());
+    }
+
+    #[test]
+    fn test_spec_round_trip() {
+        for spec in ["human:Alice", "ai:easy:Bob", "ai:hard:KI", "optimal:Opti", "winning:Win", "rollout:50:Monty",
+                     "rollout:50:7:Monty", "rollout:50:7:250:Monty", "rollout:50:0:250:M:1"].iter() {
+            assert_eq!(from_spec(spec).unwrap().spec().as_deref(), Some(*spec));
+        }
+    }
 }
@@ -0,0 +1,263 @@
+//! Exact chances of what the dice can become in the tosses left of a turn.
+
+use dice::{Dice, Die, subsets};
+use rules::RuleSet;
+use types::{Category, DiceCategory};
+use std::collections::HashMap;
+
+/// every sorted dice of the given size
+pub fn multisets(faces: &[Die], size: usize) -> Vec<Dice> {
+    if size == 0 {
+        return vec![Dice::new()]
+    }
+    let mut result = Vec::new();
+    for (i, &face) in faces.iter().enumerate() {
+        for mut rest in multisets(&faces[i..], size - 1) {
+            rest.insert(0, face);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+/// chance that a toss of all the dice shows exactly them in any order
+pub fn probability(faces: usize, dice: &Dice) -> f64 {
+    let mut orders = (1..=dice.len()).product::<usize>() as f64;
+    let mut i = 0;
+    while i < dice.len() {
+        let same = dice[i..].iter().take_while(|&&die| die == dice[i]).count();
+        orders /= (1..=same).product::<usize>() as f64;
+        i += same;
+    }
+    orders / (faces as f64).powi(dice.len() as i32)
+}
+
+/// the dice both have, both sorted
+fn common(a: &[Die], b: &[Die]) -> Dice {
+    let mut result = Dice::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            result.push(a[i]);
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Every outcome of a toss of all dice, every keep and which outcomes a
+/// keep leads to: the transition matrix of a reroll.
+pub struct TossTable {
+    faces: Vec<Die>,
+    outcomes: Vec<Dice>,
+    outcome_index: HashMap<Dice, usize>,
+    /// every multiset of kept dice, the empty one first
+    keeps: Vec<Dice>,
+    keep_index: HashMap<Dice, usize>,
+    /// the outcomes a keep leads to with their probability
+    rolls: Vec<Vec<(usize, f64)>>,
+    /// the distinct keeps of every outcome
+    outcome_keeps: Vec<Vec<usize>>,
+}
+
+impl TossTable {
+    pub fn new(faces: &[Die], dice_num: usize) -> TossTable {
+        let outcomes = multisets(faces, dice_num);
+        let outcome_index: HashMap<Dice, usize> = outcomes.iter().cloned().enumerate().map(|(i, d)| (d, i)).collect();
+        let keeps: Vec<Dice> = (0..=dice_num).flat_map(|size| multisets(faces, size)).collect();
+        let keep_index: HashMap<Dice, usize> = keeps.iter().cloned().enumerate().map(|(i, k)| (k, i)).collect();
+        let rolls = keeps.iter().map(|keep| {
+            multisets(faces, dice_num - keep.len()).into_iter().map(|roll| {
+                let probability = probability(faces.len(), &roll);
+                let mut dice = keep.clone();
+                dice.extend(roll);
+                dice.sort_unstable();
+                (outcome_index[&dice], probability)
+            }).collect()
+        }).collect();
+        let outcome_keeps = outcomes.iter()
+            .map(|dice| subsets(dice).iter().map(|keep| keep_index[keep]).collect())
+            .collect();
+        TossTable { faces: faces.to_vec(), outcomes, outcome_index, keeps, keep_index, rolls, outcome_keeps }
+    }
+
+    /// whether the table is the one of the dice
+    pub fn fits(&self, faces: &[Die], dice_num: usize) -> bool {
+        self.faces == faces && self.outcomes[0].len() == dice_num
+    }
+
+    pub fn outcomes(&self) -> &[Dice] {
+        &self.outcomes
+    }
+
+    /// the index of the dice in `outcomes`, in any order
+    pub fn outcome(&self, dice: &Dice) -> Option<usize> {
+        let mut sorted = dice.clone();
+        sorted.sort_unstable();
+        self.outcome_index.get(&sorted).cloned()
+    }
+
+    pub fn keeps(&self) -> &[Dice] {
+        &self.keeps
+    }
+
+    /// the index of the dice in `keeps`, in any order
+    pub fn keep(&self, dice: &Dice) -> Option<usize> {
+        let mut sorted = dice.clone();
+        sorted.sort_unstable();
+        self.keep_index.get(&sorted).cloned()
+    }
+
+    /// the outcomes the keep leads to with their probability
+    pub fn rolls(&self, keep: usize) -> &[(usize, f64)] {
+        &self.rolls[keep]
+    }
+
+    /// the keeps the outcome allows
+    pub fn outcome_keeps(&self, outcome: usize) -> &[usize] {
+        &self.outcome_keeps[outcome]
+    }
+
+    /// expected value of every keep when the outcomes are worth the values
+    pub fn keep_values(&self, values: &[f64]) -> Vec<f64> {
+        self.rolls.iter()
+            .map(|roll| roll.iter().map(|&(outcome, probability)| probability * values[outcome]).sum())
+            .collect()
+    }
+
+    /// what every outcome is worth with the tosses left, when the outcomes
+    /// are worth the values at the end and the best keep is always taken
+    pub fn best_values(&self, mut values: Vec<f64>, remaining_tosses: u8) -> Vec<f64> {
+        for _ in 0..remaining_tosses {
+            let keep_values = self.keep_values(&values);
+            values = self.outcome_keeps.iter()
+                .map(|keeps| keeps.iter().map(|&keep| keep_values[keep]).fold(f64::MIN, f64::max))
+                .collect();
+        }
+        values
+    }
+
+    /// the keep of the dice with the best expected value with the tosses
+    /// left, `None` if the dice are not an outcome of the table
+    pub fn best_keep(&self, values: Vec<f64>, remaining_tosses: u8, dice: &Dice) -> Option<Dice> {
+        let outcome = self.outcome(dice)?;
+        let keep_values = self.keep_values(&self.best_values(values, remaining_tosses.max(1) - 1));
+        self.outcome_keeps[outcome].iter().cloned()
+            .max_by(|&a, &b| keep_values[a].partial_cmp(&keep_values[b]).expect("values are numbers"))
+            .map(|keep| self.keeps[keep].clone())
+    }
+
+    /// Chance that the dice reach the goal in the tosses, the first toss
+    /// rolls all dice but the kept ones and the best dice are kept after
+    /// it. 0 if the kept dice are more than all dice.
+    pub fn chance<F: Fn(&Dice) -> bool>(&self, kept: &Dice, tosses: u8, goal: F) -> f64 {
+        let keep = match self.keep(kept) {
+            Some(keep) if tosses > 0 => keep,
+            _ => return 0f64,
+        };
+        let values = self.outcomes.iter().map(|dice| if goal(dice) { 1f64 } else { 0f64 }).collect();
+        let values = self.best_values(values, tosses - 1);
+        self.rolls[keep].iter().map(|&(outcome, probability)| probability * values[outcome]).sum()
+    }
+}
+
+/// Chance that `dice_num` dice show at least the wanted dice within the
+/// tosses, when the kept dice stay for the first toss and after that the
+/// dice of the wanted ones are kept.
+pub fn dice_chance(faces: &[Die], dice_num: u8, kept: &Dice, wanted: &Dice, tosses: u8) -> f64 {
+    let dice_num = dice_num as usize;
+    if wanted.len() > dice_num || kept.len() > dice_num || tosses == 0
+        || wanted.iter().any(|die| !faces.contains(die)) {
+        return 0f64
+    }
+    let mut wanted = wanted.clone();
+    wanted.sort_unstable();
+    let mut kept = kept.clone();
+    kept.sort_unstable();
+    // the states are the parts of the wanted dice that are already there
+    let states = subsets(&wanted);
+    let state_index: HashMap<Dice, usize> = states.iter().cloned().enumerate().map(|(i, s)| (s, i)).collect();
+    let toss = |held: &Dice| {
+        let mut row = vec![0f64; states.len()];
+        for roll in multisets(faces, dice_num - held.len()) {
+            let mut dice = held.clone();
+            dice.extend(roll.iter().cloned());
+            dice.sort_unstable();
+            row[state_index[&common(&dice, &wanted)]] += probability(faces.len(), &roll);
+        }
+        row
+    };
+    let matrix: Vec<Vec<f64>> = states.iter().map(&toss).collect();
+    let mut chances = toss(&kept);
+    for _ in 1..tosses {
+        let mut next = vec![0f64; states.len()];
+        for (from, &chance) in chances.iter().enumerate() {
+            for (to, &step) in matrix[from].iter().enumerate() {
+                next[to] += chance * step;
+            }
+        }
+        chances = next;
+    }
+    chances[state_index[&wanted]]
+}
+
+/// chance that the dice fit the category within the tosses, with the kept
+/// dice staying for the first toss and the best keeps after it
+pub fn category_chance(rules: &RuleSet, kept: &Dice, category: Category, tosses: u8) -> f64 {
+    if kept.len() > rules.dice_num as usize {
+        return 0f64
+    }
+    TossTable::new(&rules.dice_faces, rules.dice_num as usize)
+        .chance(kept, tosses, |dice| dice.is_category(rules, &category))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+
+    const FACES: [Die; 6] = [1, 2, 3, 4, 5, 6];
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_probability() {
+        assert_eq!(multisets(&FACES, 5).len(), 252);
+        let total: f64 = multisets(&FACES, 3).iter().map(|dice| probability(6, dice)).sum();
+        assert!(close(total, 1.0));
+        assert!(close(probability(6, &vec![1, 2]), 2.0 / 36.0));
+    }
+
+    #[test]
+    fn test_dice_chance() {
+        assert!(close(dice_chance(&FACES, 2, &vec![], &vec![1, 2], 1), 2.0 / 36.0));
+        // every die becomes a six on its own
+        let six = 1.0 - (5f64 / 6.0).powi(3);
+        assert!(close(dice_chance(&FACES, 5, &vec![], &vec![6; 5], 3), six.powi(5)));
+        assert!(close(dice_chance(&FACES, 5, &vec![6, 6], &vec![6; 5], 3), six.powi(3)));
+        // a kept one is only rerolled after the first toss
+        assert!(close(dice_chance(&FACES, 1, &vec![1], &vec![6], 2), 1.0 / 6.0));
+        assert_eq!(dice_chance(&FACES, 2, &vec![], &vec![6; 3], 3), 0.0);
+        assert_eq!(dice_chance(&FACES, 5, &vec![], &vec![7], 3), 0.0);
+    }
+
+    #[test]
+    fn test_category_chance() {
+        let rules = RuleSet::yahtzee();
+        let yahtzee = category_chance(&rules, &vec![], Yahtzee, 3);
+        assert!((yahtzee - 0.046029).abs() < 1e-6, "{}", yahtzee);
+        assert!(close(category_chance(&rules, &vec![], Yahtzee, 1), 6.0 / 7776.0));
+        assert!(close(category_chance(&rules, &vec![], Chance, 1), 1.0));
+        assert!(close(category_chance(&rules, &vec![3, 3, 3, 3, 3], Yahtzee, 1), 1.0));
+        let table = TossTable::new(&FACES, 5);
+        assert_eq!(table.chance(&vec![4, 4, 4, 4], 1, |dice| dice.is_category(&rules, &Yahtzee)),
+                   category_chance(&rules, &vec![4, 4, 4, 4], Yahtzee, 1));
+    }
+}
@@ -0,0 +1,192 @@
+use dice::Dice;
+use notation::{GameRecord, write_box, write_dice};
+use player::Player;
+use session::{Action, Checked, GameSession};
+use types::{Category, ColumnEntry};
+use rand::SeedableRng;
+use rand::rngs::SmallRng;
+
+/// What was decided at one point of a turn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Choice {
+    Announce(Option<(usize, Category)>),
+    Keep(Dice),
+    Entry(ColumnEntry),
+}
+
+impl Choice {
+    /// in the notation of a record: `!Chance@4`, `k55`, `Fives=15`
+    pub fn write(&self) -> String {
+        match *self {
+            Choice::Announce(Some((column, category))) => format!("!{}", write_box(column, category)),
+            Choice::Announce(None) => String::from("!-"),
+            Choice::Keep(ref keep) => format!("k{}", write_dice(keep)),
+            Choice::Entry((column, (category, value))) => format!("{}={}", write_box(column, category), value),
+        }
+    }
+}
+
+/// A decision of the record next to the one of the reviewer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decision {
+    pub turn: usize,
+    pub seat: usize,
+    /// the toss the dice are from
+    pub toss: u8,
+    pub dice: Dice,
+    pub actual: Choice,
+    pub suggested: Choice,
+}
+
+impl Decision {
+    pub fn agrees(&self) -> bool {
+        self.actual == self.suggested
+    }
+}
+
+/// Every decision of a recorded game with what the reviewer would have done.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Review {
+    pub names: Vec<String>,
+    pub reviewer: String,
+    pub decisions: Vec<Decision>,
+}
+
+impl Review {
+    /// decisions where the reviewer agrees with the record
+    pub fn agreed(&self) -> usize {
+        self.decisions.iter().filter(|decision| decision.agrees()).count()
+    }
+
+    /// one line per decision, differences are marked with `*`
+    pub fn write(&self) -> String {
+        let mut out = format!("{:<7} {:<12} {:<5} {:<8} {:<18} {}\n",
+                              "turn", "seat", "toss", "dice", "actual", self.reviewer);
+        for decision in &self.decisions {
+            let name = self.names.get(decision.seat).map_or("?", |name| name.as_str());
+            let line = format!("{:<7} {:<12} {:<5} {:<8} {:<18} {:<18}",
+                               format!("{}.{}", decision.turn + 1, decision.seat + 1), name,
+                               decision.toss, write_dice(&decision.dice),
+                               decision.actual.write(), decision.suggested.write());
+            out.push_str(line.trim_end());
+            if !decision.agrees() {
+                out.push_str(" *");
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("{} of {} decisions agree\n", self.agreed(), self.decisions.len()));
+        out
+    }
+}
+
+/// Plays the record again with its own dice and asks the reviewer at every
+/// decision what it would do instead.
+pub fn review(record: &GameRecord, reviewer: &dyn Player) -> Checked<Review> {
+    let script = record.states.iter()
+        .map(|state| state.history.iter().flat_map(|turn| turn.tosses.iter().cloned()).collect())
+        .collect();
+    // the rng only tosses for seats that are disqualified
+    let mut session = GameSession::scripted(record.rules.clone(), script, SmallRng::seed_from_u64(0));
+    let names = &record.names;
+    let mut played = vec![0; record.states.len()];
+    let mut decisions = Vec::new();
+    loop {
+        let action = session.pending_action();
+        let seat = match action {
+            Action::Announce { seat } | Action::Keep { seat, .. } | Action::Entry { seat } => seat,
+            Action::Finished => break,
+        };
+        let turn = match record.states[seat].history.get(played[seat]) {
+            Some(turn) => turn,
+            None if record.states[seat].disqualified => {
+                session.disqualify();
+                continue
+            }
+            None => break,
+        };
+        let dice = session.dice().clone();
+        let kept = session.position().keeps.len();
+        let view = session.view(names);
+        let (actual, suggested) = match action {
+            Action::Announce { .. } => {
+                (Choice::Announce(turn.announcement), Choice::Announce(reviewer.announce(&view, &dice)))
+            }
+            Action::Keep { remaining_tosses, .. } => match turn.keeps.get(kept) {
+                Some(keep) => {
+                    let mut suggested = reviewer.decide_keep(&view, remaining_tosses, &dice);
+                    suggested.sort_unstable();
+                    (Choice::Keep(keep.clone()), Choice::Keep(suggested))
+                }
+                // the turn was forfeited for a keep that was not allowed
+                None => {
+                    session.forfeit();
+                    played[seat] += 1;
+                    continue
+                }
+            },
+            Action::Entry { .. } => {
+                (Choice::Entry(turn.entry), Choice::Entry(reviewer.decide_entry(&view, &dice)))
+            }
+            Action::Finished => unreachable!(),
+        };
+        decisions.push(Decision {
+            turn: session.turn(), seat, toss: session.toss(), dice,
+            actual: actual.clone(), suggested,
+        });
+        match actual {
+            Choice::Announce(announcement) => session.apply_announce(announcement)?,
+            Choice::Keep(keep) => session.apply_keep(keep)?,
+            Choice::Entry(_) if turn.forfeited => session.forfeit(),
+            Choice::Entry(entry) => session.apply_entry(entry)?,
+        }
+        if let Action::Entry { .. } = action {
+            played[seat] += 1;
+        }
+    }
+    Ok(Review { names: names.clone(), reviewer: reviewer.name().to_string(), decisions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai::{Difficulty, YahtzeeAI};
+    use game::Game;
+    use rules::RuleSet;
+    use save::SeededRng;
+
+    fn record(rules: RuleSet, seed: u64) -> GameRecord {
+        let players: Vec<Box<dyn Player>> = vec![
+            Box::new(YahtzeeAI::with_difficulty("A", Difficulty::Easy)),
+            Box::new(YahtzeeAI::with_difficulty("B", Difficulty::Easy)),
+        ];
+        let mut game: Game<SeededRng> = Game::with_seed(players, rules, seed);
+        game.run();
+        GameRecord::of(&game)
+    }
+
+    #[test]
+    fn test_review_same_player() {
+        let record = record(RuleSet::yahtzee(), 3);
+        let reviewer = YahtzeeAI::with_difficulty("A", Difficulty::Easy);
+        let review = review(&record, &reviewer).unwrap();
+        let turns: usize = record.states.iter().map(|state| state.history.len()).sum();
+        let keeps: usize = record.states.iter()
+            .flat_map(|state| state.history.iter())
+            .map(|turn| turn.keeps.len())
+            .sum();
+        assert_eq!(review.decisions.len(), turns + keeps);
+        assert_eq!(review.agreed(), review.decisions.len());
+        assert!(review.write().ends_with(&format!("{0} of {0} decisions agree\n", turns + keeps)));
+    }
+
+    #[test]
+    fn test_review_announce() {
+        let record = record(RuleSet::kniffel(), 5);
+        let reviewer = YahtzeeAI::with_difficulty("B", Difficulty::Easy);
+        let review = review(&record, &reviewer).unwrap();
+        assert!(review.decisions.iter().any(|decision| matches!(decision.actual, Choice::Announce(_))));
+        let text = review.write();
+        assert!(text.starts_with("turn"));
+        assert_eq!(text.lines().count(), review.decisions.len() + 2);
+    }
+}
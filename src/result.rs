@@ -1,31 +1,47 @@
-use types::{Category, Entry, MyEntry, Value};
+use types::{Category, DiceCategory, Entry, MyEntry, Value};
+use types::Category::*;
+use dice::Dice;
+use rules::RuleSet;
 use std::collections::HashMap;
 
-pub const BONUS_REACH: u32 = 63;
-pub const BONUS_VALUE: u32 = 35;
-
+#[derive(Clone)]
 pub struct Result {
+    rules: RuleSet,
     map: HashMap<Category, Value>,
     bonus: bool,
+    yahtzee_bonus: u8,
 }
 
 impl Result {
-    pub fn new() -> Result {
-        Result { map: HashMap::new(), bonus: false }
+    pub fn new(rules: &RuleSet) -> Result {
+        Result { rules: rules.clone(), map: HashMap::new(), bonus: false, yahtzee_bonus: 0 }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
     }
 
     pub fn has_bonus(&self) -> bool {
-        self.value_upper() >= BONUS_REACH
+        self.bonus
+    }
+
+    /// rules without a bonus value, like Generala, have no bonus to reach
+    fn reaches_bonus(&self) -> bool {
+        self.rules.bonus_value > 0 && self.value_upper() >= self.rules.bonus_reach
     }
 
     pub fn bonus_progress(&self) -> i32 {
         let mut progress = 0;
         for (&cat, &val) in self.map.iter() {
-            progress += (cat, val).upper_above_bonus()
+            progress += (cat, val).upper_above_bonus(&self.rules)
         }
         progress
     }
 
+    pub fn yahtzee_bonus(&self) -> u8 {
+        self.yahtzee_bonus
+    }
+
     pub fn value_upper(&self) -> u32 {
         let mut value: u32 = 0;
         for &cat in Category::upper_into_iter() {
@@ -38,7 +54,7 @@ impl Result {
 
     pub fn value_lower(&self) -> u32 {
         let mut value: u32 = 0;
-        for &cat in Category::lower_into_iter() {
+        for cat in self.rules.lower_categories() {
             if let Some(&v) = self.map.get(&cat) {
                 value += v as u32
             }
@@ -48,17 +64,80 @@ impl Result {
 
     pub fn value(&self) -> u32 {
         let mut value = self.value_upper();
-        if value >= BONUS_REACH {
-            value += BONUS_VALUE
+        if value >= self.rules.bonus_reach {
+            value += self.rules.bonus_value
         }
         value += self.value_lower();
+        value += self.yahtzee_bonus as u32 * self.rules.yahtzee_bonus_value;
         value
     }
 
-    pub fn add(&mut self, entry: Entry) {
+    /// dice are a Yahtzee while the Yahtzee box is already filled
+    pub fn is_joker(&self, dice: &Dice) -> bool {
+        self.rules.joker && self.has(Yahtzee) && dice.is_category(&self.rules, &Yahtzee)
+    }
+
+    /// another Yahtzee after the Yahtzee box was scored with 50
+    pub fn earns_yahtzee_bonus(&self, dice: &Dice) -> bool {
+        self.rules.yahtzee_bonus_value > 0
+            && self.get(Yahtzee) == Some(self.rules.yahtzee_value)
+            && dice.is_category(&self.rules, &Yahtzee)
+    }
+
+    /// Joker rules: the matching upper box has to be used if it is open,
+    /// otherwise any lower box and only if those are all filled an upper box
+    pub fn can_write(&self, dice: &Dice, category: Category) -> bool {
+        if self.has(category) {
+            return false
+        }
+        if !self.is_joker(dice) {
+            return true
+        }
+        let upper = Category::upper_of(dice[0]);
+        if self.rules.categories.contains(&upper) && !self.has(upper) {
+            category == upper
+        } else if category.is_lower() {
+            true
+        } else {
+            self.rules.lower_categories().into_iter().all(|cat| self.has(cat))
+        }
+    }
+
+    pub fn entry_of_category(&self, dice: &Dice, category: Category) -> Entry {
+        if category == DoubleYahtzee && self.get(Yahtzee) != Some(self.rules.yahtzee_value) {
+            // only a second Yahtzee counts double
+            (category, 0)
+        } else if self.is_joker(dice) {
+            (category, dice.joker_value_of_category(&self.rules, &category))
+        } else {
+            dice.entry_of_category(&self.rules, category)
+        }
+    }
+
+    pub fn possible_entries(&self, dice: &Dice) -> Vec<Entry> {
+        self.rules.categories.iter()
+            .filter(|&&cat| self.can_write(dice, cat))
+            .map(|&cat| self.entry_of_category(dice, cat))
+            .collect()
+    }
+
+    pub fn add(&mut self, dice: &Dice, entry: Entry) {
+        if self.earns_yahtzee_bonus(dice) {
+            self.yahtzee_bonus += 1;
+        }
         let (cat, val) = entry;
         self.map.insert(cat, val);
-        self.bonus = self.value_upper() >= BONUS_REACH;
+        self.bonus = self.reaches_bonus();
+    }
+
+    /// takes back the last `add` of the dice, including the bonuses it gave
+    pub fn remove(&mut self, dice: &Dice, category: Category) -> Option<Value> {
+        let value = self.map.remove(&category)?;
+        if self.earns_yahtzee_bonus(dice) {
+            self.yahtzee_bonus = self.yahtzee_bonus.saturating_sub(1);
+        }
+        self.bonus = self.reaches_bonus();
+        Some(value)
     }
 
     pub fn has(&self, category: Category) -> bool {
@@ -66,6 +145,136 @@ impl Result {
     }
 
     pub fn get(&self, category: Category) -> Option<u8> {
-        self.map.get(&category).map(|&u| u)
+        self.map.get(&category).copied()
+    }
+}
+
+impl Default for Result {
+    fn default() -> Result {
+        Result::new(&RuleSet::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yahtzee_bonus() {
+        let mut result = Result::default();
+        let yahtzee = vec![4, 4, 4, 4, 4];
+        result.add(&yahtzee, (Yahtzee, 50));
+        assert_eq!(result.yahtzee_bonus(), 0);
+        result.add(&yahtzee, (Fours, 20));
+        assert_eq!(result.yahtzee_bonus(), 1);
+        assert_eq!(result.value(), 50 + 20 + 100);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut result = Result::default();
+        let yahtzee = vec![6, 6, 6, 6, 6];
+        result.add(&vec![5, 5, 5, 5, 1], (Fives, 20));
+        result.add(&vec![4, 4, 4, 4, 1], (Fours, 16));
+        result.add(&yahtzee, (Yahtzee, 50));
+        result.add(&yahtzee, (Sixes, 30));
+        assert!(result.has_bonus());
+        assert_eq!(result.yahtzee_bonus(), 1);
+        assert_eq!(result.remove(&yahtzee, Sixes), Some(30));
+        assert!(!result.has_bonus());
+        assert_eq!(result.yahtzee_bonus(), 0);
+        assert_eq!(result.remove(&yahtzee, Yahtzee), Some(50));
+        assert_eq!(result.remove(&yahtzee, Yahtzee), None);
+        assert_eq!(result.value(), 36);
+    }
+
+    #[test]
+    fn test_no_yahtzee_bonus_after_scratch() {
+        let mut result = Result::default();
+        let yahtzee = vec![2, 2, 2, 2, 2];
+        result.add(&vec![1, 2, 3, 5, 5], (Yahtzee, 0));
+        result.add(&yahtzee, (Twos, 10));
+        assert_eq!(result.yahtzee_bonus(), 0);
+        assert!(result.is_joker(&yahtzee));
+    }
+
+    #[test]
+    fn test_joker_forced_upper() {
+        let mut result = Result::default();
+        let yahtzee = vec![3, 3, 3, 3, 3];
+        result.add(&yahtzee, (Yahtzee, 50));
+        assert_eq!(result.possible_entries(&yahtzee), vec![(Threes, 15)]);
+        assert!(!result.can_write(&yahtzee, FullHouse));
+    }
+
+    #[test]
+    fn test_joker_without_upper_box() {
+        let rules = RuleSet { categories: vec![Yahtzee, Chance], ..RuleSet::default() };
+        let mut result = Result::new(&rules);
+        let yahtzee = vec![3, 3, 3, 3, 3];
+        result.add(&yahtzee, (Yahtzee, 50));
+        assert_eq!(result.possible_entries(&yahtzee), vec![(Chance, 15)]);
+    }
+
+    #[test]
+    fn test_joker_lower_full_value() {
+        let mut result = Result::default();
+        let yahtzee = vec![3, 3, 3, 3, 3];
+        result.add(&yahtzee, (Yahtzee, 50));
+        result.add(&vec![1, 3, 3, 3, 5], (Threes, 9));
+        assert!(!result.can_write(&yahtzee, Ones));
+        assert_eq!(result.entry_of_category(&yahtzee, FullHouse), (FullHouse, 25));
+        assert_eq!(result.entry_of_category(&yahtzee, SmallStraight), (SmallStraight, 30));
+        assert_eq!(result.entry_of_category(&yahtzee, LargeStraght), (LargeStraght, 40));
+        assert_eq!(result.entry_of_category(&yahtzee, FourOfAKind), (FourOfAKind, 15));
+    }
+
+    #[test]
+    fn test_joker_upper_when_lower_full() {
+        let mut result = Result::default();
+        let yahtzee = vec![6, 6, 6, 6, 6];
+        for cat in RuleSet::default().lower_categories() {
+            result.add(&vec![1, 1, 2, 2, 3], (cat, 0));
+        }
+        result.add(&vec![6, 6, 1, 1, 2], (Sixes, 12));
+        assert!(result.can_write(&yahtzee, Ones));
+        assert_eq!(result.entry_of_category(&yahtzee, Ones), (Ones, 0));
+    }
+
+    #[test]
+    fn test_house_rules() {
+        let rules = RuleSet {
+            bonus_reach: 10,
+            bonus_value: 5,
+            yahtzee_bonus_value: 0,
+            ..RuleSet::default()
+        };
+        let mut result = Result::new(&rules);
+        let yahtzee = vec![5, 5, 5, 5, 5];
+        result.add(&yahtzee, (Yahtzee, 50));
+        result.add(&yahtzee, (Fives, 25));
+        assert!(result.has_bonus());
+        assert_eq!(result.yahtzee_bonus(), 0);
+        assert_eq!(result.value(), 50 + 25 + 5);
+    }
+
+    #[test]
+    fn test_generala_no_bonus() {
+        let mut result = Result::new(&RuleSet::generala());
+        result.add(&vec![1, 2, 3, 3, 5], (Ones, 1));
+        assert!(!result.has_bonus());
+        assert_eq!(result.value(), 1);
+    }
+
+    #[test]
+    fn test_yatzy_no_joker() {
+        let mut result = Result::new(&RuleSet::yatzy());
+        let yatzy = vec![6, 6, 6, 6, 6];
+        result.add(&yatzy, (Yahtzee, 50));
+        result.add(&vec![6, 6, 6, 5, 5], (Sixes, 18));
+        assert!(!result.is_joker(&yatzy));
+        assert!(!result.earns_yahtzee_bonus(&yatzy));
+        assert_eq!(result.entry_of_category(&yatzy, FullHouse), (FullHouse, 0));
+        assert_eq!(result.possible_entries(&yatzy).len(), 13);
     }
 }
@@ -0,0 +1,256 @@
+use ai::{Difficulty, YahtzeeAI};
+use dice::{Dice, subsets};
+use player::Player;
+use session::{Action, Checked, GameSession, Position};
+use table::TableView;
+use types::{Category, ColumnEntry};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use std::time::{Duration, Instant};
+
+/// points an instant win is counted as
+const INSTANT_WIN_VALUE: f64 = 10000.0;
+
+/// How a `RolloutAI` plays out its candidates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rollouts {
+    /// games played to the end for every candidate
+    pub count: usize,
+    /// the n-th rollout of every candidate of a decision tosses with a seed
+    /// made from this one, the turn, seat and toss of the decision and n, so
+    /// that the decisions of a game do not share their dice
+    pub seed: u64,
+    /// stops the rollouts of a decision early, every candidate still gets as many
+    pub budget: Option<Duration>,
+    /// how the rollouts decide
+    pub policy: Difficulty,
+}
+
+impl Default for Rollouts {
+    fn default() -> Rollouts {
+        Rollouts { count: 100, seed: 0, budget: None, policy: Difficulty::Easy }
+    }
+}
+
+/// Tries every keep and entry in games played to the end by a quick
+/// `YahtzeeAI` and decides for the one with the highest average score. More
+/// rollouts play stronger, and as nothing has to be solved it plays every
+/// variant.
+pub struct RolloutAI {
+    name: String,
+    rollouts: Rollouts,
+    policy: YahtzeeAI,
+}
+
+impl RolloutAI {
+    pub fn new(name: &str) -> RolloutAI {
+        RolloutAI::with_rollouts(name, Rollouts::default())
+    }
+
+    pub fn with_rollouts(name: &str, rollouts: Rollouts) -> RolloutAI {
+        RolloutAI { name: String::from(name), rollouts, policy: YahtzeeAI::with_difficulty(name, rollouts.policy) }
+    }
+
+    pub fn rollouts(&self) -> Rollouts {
+        self.rollouts
+    }
+
+    /// The candidate with the highest average. The candidates are played in
+    /// rounds with the same seed, so all have as many rollouts when the
+    /// budget runs out.
+    fn best<T, F>(&self, table: &TableView, position: Position, mut candidates: Vec<T>, decide: F) -> Option<T>
+        where F: Fn(&mut GameSession<XorShiftRng>, &T) -> Checked<()> {
+        if candidates.len() < 2 {
+            return candidates.pop()
+        }
+        let decision = self.decision_seed(&position);
+        // the others do not change the score of the seat
+        let state = table.state(table.seat()).clone();
+        let position = Position { seat: 0, ..position };
+        let start = Instant::now();
+        let mut totals = vec![0f64; candidates.len()];
+        for round in (0..).take_while(|&round| self.plays_round(round, start.elapsed())) {
+            let seed = decision.wrapping_add(round as u64);
+            for (candidate, total) in candidates.iter().zip(totals.iter_mut()) {
+                let mut session = GameSession::resume(table.rules().clone(), vec![state.clone()],
+                                                      XorShiftRng::seed_from_u64(seed), position.clone());
+                *total += match decide(&mut session, candidate) {
+                    Ok(()) => self.play_out(session),
+                    Err(_) => f64::MIN,
+                };
+            }
+        }
+        let best = totals.iter().enumerate()
+            .fold(0, |best, (i, &total)| if total > totals[best] { i } else { best });
+        Some(candidates.swap_remove(best))
+    }
+
+    /// whether the round is played after the time the rounds before took,
+    /// the first always is
+    fn plays_round(&self, round: usize, elapsed: Duration) -> bool {
+        round < self.rollouts.count.max(1)
+            && (round == 0 || self.rollouts.budget.is_none_or(|budget| elapsed < budget))
+    }
+
+    /// the seed of the first round of the decision
+    fn decision_seed(&self, position: &Position) -> u64 {
+        let decision = ((position.turn as u64) << 24) | ((position.seat as u64) << 8) | position.toss as u64;
+        self.rollouts.seed.wrapping_add(decision.wrapping_mul(0x9e37_79b9_7f4a_7c15))
+    }
+
+    /// the final value of the only seat when the policy plays the rest
+    fn play_out(&self, mut session: GameSession<XorShiftRng>) -> f64 {
+        loop {
+            let dice = session.dice().clone();
+            let applied = match session.pending_action() {
+                Action::Announce { .. } => {
+                    let announcement = self.policy.announce(&session.view(&[]), &dice);
+                    session.apply_announce(announcement)
+                }
+                Action::Keep { remaining_tosses, .. } => {
+                    let keep = self.policy.decide_keep(&session.view(&[]), remaining_tosses, &dice);
+                    session.apply_keep(keep)
+                }
+                Action::Entry { .. } => {
+                    let entry = self.policy.decide_entry(&session.view(&[]), &dice);
+                    session.apply_entry(entry)
+                }
+                Action::Finished => break,
+            };
+            if applied.is_err() {
+                session.forfeit();
+            }
+        }
+        let card = session.card();
+        card.value() as f64 + if card.has_won() { INSTANT_WIN_VALUE } else { 0f64 }
+    }
+
+    /// where the game of the table stands with the dice
+    fn position(table: &TableView, dice: &Dice, action: Action, remaining_tosses: u8) -> Position {
+        let toss = table.toss();
+        Position {
+            turn: table.turn(),
+            seat: table.seat(),
+            dice: dice.clone(),
+            toss,
+            toss_num: toss + remaining_tosses,
+            // the tosses before are not known, a turn needs one
+            tosses: vec![dice.clone()],
+            keeps: Vec::new(),
+            timeouts: Vec::new(),
+            action,
+            turn_bank: table.state(table.seat()).roll_bank,
+        }
+    }
+}
+
+impl Player for RolloutAI {
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice {
+        let action = Action::Keep { seat: 0, remaining_tosses };
+        let position = RolloutAI::position(table, dice, action, remaining_tosses);
+        self.best(table, position, subsets(dice), |session, keep| session.apply_keep(keep.clone()))
+            .unwrap_or_else(|| dice.clone())
+    }
+
+    fn decide_entry(&self, table: &TableView, dice: &Dice) -> ColumnEntry {
+        let position = RolloutAI::position(table, dice, Action::Entry { seat: 0 }, 0);
+        let entries = table.card().possible_entries(dice);
+        match self.best(table, position, entries, |session, &entry| session.apply_entry(entry)) {
+            Some(entry) => entry,
+            None => self.policy.decide_entry(table, dice),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `None` for a policy other than the default or a name that reads as
+    /// a number, the budget is kept in whole milliseconds
+    fn spec(&self) -> Option<String> {
+        let rollouts = self.rollouts;
+        if rollouts.policy != Rollouts::default().policy || self.name.parse::<u64>().is_ok() {
+            return None
+        }
+        let mut spec = format!("rollout:{}", rollouts.count);
+        if rollouts.seed != Rollouts::default().seed || rollouts.budget.is_some() {
+            spec.push_str(&format!(":{}", rollouts.seed));
+        }
+        if let Some(budget) = rollouts.budget {
+            spec.push_str(&format!(":{}", budget.as_millis()));
+        }
+        Some(format!("{}:{}", spec, self.name))
+    }
+
+    fn announce(&self, table: &TableView, dice: &Dice) -> Option<(usize, Category)> {
+        self.policy.announce(table, dice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dice::is_subset;
+    use game::GameState;
+    use rules::RuleSet;
+    use types::Category::*;
+
+    fn player(count: usize) -> RolloutAI {
+        RolloutAI::with_rollouts("Rollout", Rollouts { count, ..Rollouts::default() })
+    }
+
+    #[test]
+    fn test_decisions() {
+        let rules = RuleSet { categories: vec![Yahtzee, Chance], ..RuleSet::yahtzee() };
+        let mut states = vec![GameState::new(&rules)];
+        states[0].card.set_toss(2);
+        let table = TableView::new(&states, &[], 0, 0);
+        let player = player(400);
+        assert_eq!(player.decide_keep(&table, 1, &vec![1, 1, 1, 6, 6]), vec![6, 6]);
+        // Chance is worth more later than a Yahtzee is
+        assert_eq!(player.decide_entry(&table, &vec![1, 2, 3, 4, 5]), (0, (Yahtzee, 0)));
+        assert_eq!(player.decide_entry(&table, &vec![4, 4, 4, 4, 4]), (0, (Yahtzee, 50)));
+        assert_eq!(player.spec().as_deref(), Some("rollout:400:Rollout"));
+    }
+
+    #[test]
+    fn test_budget() {
+        let budget = Rollouts { count: 10, budget: Some(Duration::from_millis(50)), ..Rollouts::default() };
+        let player = RolloutAI::with_rollouts("Rollout", budget);
+        assert!(player.plays_round(0, Duration::from_secs(1)));
+        assert!(player.plays_round(5, Duration::from_millis(10)));
+        assert!(!player.plays_round(5, Duration::from_millis(50)));
+        assert!(!player.plays_round(10, Duration::from_millis(0)));
+        let unlimited = RolloutAI::with_rollouts("Rollout", Rollouts { count: 0, ..Rollouts::default() });
+        assert!(unlimited.plays_round(0, Duration::from_secs(1)));
+        assert!(!unlimited.plays_round(1, Duration::from_secs(0)));
+        assert_eq!(player.spec().as_deref(), Some("rollout:10:0:50:Rollout"));
+    }
+
+    #[test]
+    fn test_zero_budget() {
+        // a variant without a solver, announcing and several columns, one round
+        let rules = RuleSet::kniffel();
+        let mut states = vec![GameState::new(&rules), GameState::new(&rules)];
+        states[1].card.set_toss(1);
+        let table = TableView::new(&states, &[], 1, 0);
+        let player = RolloutAI::with_rollouts("Rollout", Rollouts {
+            count: 1_000_000, budget: Some(Duration::from_secs(0)), ..Rollouts::default()
+        });
+        let keep = player.decide_keep(&table, 2, &vec![2, 3, 3, 3, 6]);
+        assert!(is_subset(&keep, &[2, 3, 3, 3, 6]));
+    }
+
+    #[test]
+    fn test_decision_seed() {
+        let player = player(10);
+        let position = |turn, toss| Position {
+            turn, seat: 0, dice: vec![1; 5], toss, toss_num: 3, tosses: Vec::new(), keeps: Vec::new(),
+            timeouts: Vec::new(), action: Action::Entry { seat: 0 }, turn_bank: 0,
+        };
+        let first = player.decision_seed(&position(0, 1));
+        assert_ne!(first, player.decision_seed(&position(0, 2)));
+        assert_ne!(first, player.decision_seed(&position(1, 1)));
+        assert_eq!(first, player.decision_seed(&position(0, 1)));
+    }
+}
@@ -0,0 +1,253 @@
+use dice::Die;
+use types::{Category, Value};
+use types::Category::*;
+
+/// Scoring family the categories are evaluated by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Yahtzee,
+    /// Scandinavian Yatzy: of-a-kind boxes count only the matching dice,
+    /// straights are fixed runs and a full house scores the dice sum
+    Yatzy,
+    /// Latin-American Generala: fixed values with extra points when made
+    /// with the first toss and an instant win for a Generala on it
+    Generala,
+}
+
+impl Variant {
+    pub fn by_name(name: &str) -> Option<Variant> {
+        [Variant::Yahtzee, Variant::Yatzy, Variant::Generala].iter().cloned()
+            .find(|variant| format!("{:?}", variant) == name)
+    }
+}
+
+/// Order in which the boxes of a scorecard column have to be filled.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    Free,
+    /// top to bottom
+    Down,
+    /// bottom to top
+    Up,
+    /// only the box announced after the first toss
+    Announce,
+}
+
+impl Order {
+    pub fn by_name(name: &str) -> Option<Order> {
+        [Order::Free, Order::Down, Order::Up, Order::Announce].iter().cloned()
+            .find(|order| format!("{:?}", order) == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Column {
+    pub weight: u32,
+    pub order: Order,
+}
+
+impl Column {
+    pub fn new(weight: u32, order: Order) -> Column {
+        Column { weight, order }
+    }
+
+    pub fn free(weight: u32) -> Column {
+        Column::new(weight, Order::Free)
+    }
+}
+
+/// All numbers that can differ between house rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleSet {
+    pub variant: Variant,
+    /// boxes of the scorecard in order
+    pub categories: Vec<Category>,
+    /// multiplier and filling order of every scorecard column
+    pub columns: Vec<Column>,
+    pub dice_num: u8,
+    pub dice_faces: Vec<Die>,
+    pub toss_num: u8,
+    pub bonus_reach: u32,
+    pub bonus_value: u32,
+    pub full_house_value: Value,
+    pub small_straight_value: Value,
+    pub large_straight_value: Value,
+    pub full_straight_value: Value,
+    /// fixed value of Four of a Kind in Generala
+    pub poker_value: Value,
+    pub yahtzee_value: Value,
+    pub double_yahtzee_value: Value,
+    pub yahtzee_bonus_value: u32,
+    /// extra points for a straight, full house or poker made with the first toss
+    pub served_bonus: Value,
+    /// Joker rules for additional Yahtzees
+    pub joker: bool,
+    /// unused tosses are banked for later turns
+    pub save_rolls: bool,
+}
+
+impl RuleSet {
+    /// the official Yahtzee rules
+    pub fn yahtzee() -> RuleSet {
+        RuleSet {
+            variant: Variant::Yahtzee,
+            categories: vec![Ones, Twos, Threes, Fours, Fives, Sixes,
+                             ThreeOfAKind, FourOfAKind, FullHouse,
+                             SmallStraight, LargeStraght, Yahtzee, Chance],
+            columns: vec![Column::free(1)],
+            dice_num: 5,
+            dice_faces: vec![1, 2, 3, 4, 5, 6],
+            toss_num: 3,
+            bonus_reach: 63,
+            bonus_value: 35,
+            full_house_value: 25,
+            small_straight_value: 30,
+            large_straight_value: 40,
+            full_straight_value: 0,
+            poker_value: 0,
+            yahtzee_value: 50,
+            double_yahtzee_value: 0,
+            yahtzee_bonus_value: 100,
+            served_bonus: 0,
+            joker: true,
+            save_rolls: false,
+        }
+    }
+
+    /// three columns counting single, double and triple
+    pub fn triple_yahtzee() -> RuleSet {
+        RuleSet {
+            columns: vec![Column::free(1), Column::free(2), Column::free(3)],
+            ..RuleSet::yahtzee()
+        }
+    }
+
+    /// German club Kniffel with a column for every filling order
+    pub fn kniffel() -> RuleSet {
+        RuleSet {
+            columns: vec![Column::new(1, Order::Down), Column::new(1, Order::Up),
+                          Column::new(1, Order::Free), Column::new(1, Order::Announce)],
+            yahtzee_bonus_value: 0,
+            joker: false,
+            ..RuleSet::yahtzee()
+        }
+    }
+
+    /// the Scandinavian rules
+    pub fn yatzy() -> RuleSet {
+        RuleSet {
+            variant: Variant::Yatzy,
+            categories: vec![Ones, Twos, Threes, Fours, Fives, Sixes,
+                             OnePair, TwoPairs, ThreeOfAKind, FourOfAKind,
+                             SmallStraight, LargeStraght, FullHouse, Chance, Yahtzee],
+            columns: vec![Column::free(1)],
+            dice_num: 5,
+            dice_faces: vec![1, 2, 3, 4, 5, 6],
+            toss_num: 3,
+            bonus_reach: 63,
+            bonus_value: 50,
+            full_house_value: 0,
+            small_straight_value: 15,
+            large_straight_value: 20,
+            full_straight_value: 0,
+            poker_value: 0,
+            yahtzee_value: 50,
+            double_yahtzee_value: 0,
+            yahtzee_bonus_value: 0,
+            served_bonus: 0,
+            joker: false,
+            save_rolls: false,
+        }
+    }
+
+    /// Maxi Yatzy with six dice and saved rolls
+    pub fn maxi_yatzy() -> RuleSet {
+        RuleSet {
+            variant: Variant::Yatzy,
+            categories: vec![Ones, Twos, Threes, Fours, Fives, Sixes,
+                             OnePair, TwoPairs, ThreePairs, ThreeOfAKind, FourOfAKind, FiveOfAKind,
+                             SmallStraight, LargeStraght, FullStraight,
+                             FullHouse, Castle, Tower, Chance, Yahtzee],
+            columns: vec![Column::free(1)],
+            dice_num: 6,
+            dice_faces: vec![1, 2, 3, 4, 5, 6],
+            toss_num: 3,
+            bonus_reach: 84,
+            bonus_value: 50,
+            full_house_value: 0,
+            small_straight_value: 15,
+            large_straight_value: 20,
+            full_straight_value: 21,
+            poker_value: 0,
+            yahtzee_value: 100,
+            double_yahtzee_value: 0,
+            yahtzee_bonus_value: 0,
+            served_bonus: 0,
+            joker: false,
+            save_rolls: true,
+        }
+    }
+
+    /// Generala with servida bonuses and Double Generala
+    pub fn generala() -> RuleSet {
+        RuleSet {
+            variant: Variant::Generala,
+            categories: vec![Ones, Twos, Threes, Fours, Fives, Sixes,
+                             LargeStraght, FullHouse, FourOfAKind, Yahtzee, DoubleYahtzee],
+            columns: vec![Column::free(1)],
+            dice_num: 5,
+            dice_faces: vec![1, 2, 3, 4, 5, 6],
+            toss_num: 3,
+            bonus_reach: 0,
+            bonus_value: 0,
+            full_house_value: 30,
+            small_straight_value: 0,
+            large_straight_value: 20,
+            full_straight_value: 0,
+            poker_value: 40,
+            yahtzee_value: 50,
+            double_yahtzee_value: 100,
+            yahtzee_bonus_value: 0,
+            served_bonus: 5,
+            joker: false,
+            save_rolls: false,
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<RuleSet> {
+        match name {
+            "yahtzee" => Some(RuleSet::yahtzee()),
+            "triple" => Some(RuleSet::triple_yahtzee()),
+            "kniffel" => Some(RuleSet::kniffel()),
+            "yatzy" => Some(RuleSet::yatzy()),
+            "maxi" => Some(RuleSet::maxi_yatzy()),
+            "generala" => Some(RuleSet::generala()),
+            _ => None,
+        }
+    }
+
+    /// the name `by_name` knows these rules by
+    pub fn name(&self) -> Option<&'static str> {
+        ["yahtzee", "triple", "kniffel", "yatzy", "maxi", "generala"].iter().cloned()
+            .find(|&name| RuleSet::by_name(name).as_ref() == Some(self))
+    }
+
+    pub fn lower_categories(&self) -> Vec<Category> {
+        self.categories.iter().cloned().filter(Category::is_lower).collect()
+    }
+
+    /// number of turns until every box of every column is filled
+    pub fn turns(&self) -> usize {
+        self.categories.len() * self.columns.len()
+    }
+
+    pub fn max_face(&self) -> Die {
+        self.dice_faces.iter().cloned().max().unwrap_or(0)
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> RuleSet {
+        RuleSet::yahtzee()
+    }
+}
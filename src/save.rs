@@ -0,0 +1,613 @@
+use dice::{is_subset, Dice, Die};
+use clock::{Clock, TimeControl};
+use game::{Game, GameState, Timeout, ViolationPolicy};
+use player::{self, Player};
+use rules::{Column, Order, RuleSet, Variant};
+use notation;
+use session::{Action, GameSession, Position, UndoPolicy};
+use types::Category;
+use rand::{self, RngCore, SeedableRng};
+use rand_xorshift::XorShiftRng;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// first line of every save file
+const HEADER: &str = "yahtzee-save";
+const VERSION: u32 = 4;
+/// the generator of the dice, a save only resumes with the same one
+const GENERATOR: &str = "xorshift";
+
+/// Why a game could not be saved or loaded.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    /// not a save file or one of another version
+    Version(String),
+    Missing(String),
+    Invalid { key: String, value: String },
+    /// a player that can not be written as or made from a spec
+    Player(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{}", err),
+            SaveError::Version(header) => write!(f, "unsupported save file '{}'", header),
+            SaveError::Missing(key) => write!(f, "missing {}", key),
+            SaveError::Invalid { key, value } => write!(f, "invalid {} '{}'", key, value),
+            SaveError::Player(player) => write!(f, "can not save or load player {}", player),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(err: io::Error) -> SaveError {
+        SaveError::Io(err)
+    }
+}
+
+pub type Saved<T> = ::std::result::Result<T, SaveError>;
+
+/// An `XorShiftRng` that counts the numbers it gave, so that its state can
+/// be saved as the seed and the count. Unlike `SmallRng` it gives the same
+/// numbers on every platform and with every version of `rand`.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    seed: u64,
+    draws: u64,
+    rng: XorShiftRng,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> SeededRng {
+        SeededRng { seed, draws: 0, rng: XorShiftRng::seed_from_u64(seed) }
+    }
+
+    /// continues after the given number of draws
+    pub fn resume(seed: u64, draws: u64) -> SeededRng {
+        let mut rng = SeededRng::new(seed);
+        for _ in 0..draws {
+            rng.next_u64();
+        }
+        rng
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn draws(&self) -> u64 {
+        self.draws
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.next_u64() as u32
+    }
+
+    // every number is made from one draw, so the count is all it takes to resume
+    fn next_u64(&mut self) -> u64 {
+        self.draws += 1;
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> ::std::result::Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl SeedableRng for SeededRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: [u8; 8]) -> SeededRng {
+        SeededRng::new(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(seed: u64) -> SeededRng {
+        SeededRng::new(seed)
+    }
+}
+
+impl Game<SeededRng> {
+    /// the game as text in the save file format
+    pub fn save(&self) -> Saved<String> {
+        let mut out = String::new();
+        writeln!(out, "{} {}", HEADER, VERSION).expect("writing to a String");
+        write_rules(&mut out, self.rules());
+        let session = self.session();
+        let mut line = |key: &str, value: String| writeln!(out, "{}={}", key, value).expect("writing to a String");
+        line("rng", String::from(GENERATOR));
+        line("seed", session.rng().seed().to_string());
+        line("draws", session.rng().draws().to_string());
+        line("policy", match self.violation_policy() {
+            ViolationPolicy::Reask(attempts) => format!("reask:{}", attempts),
+            ViolationPolicy::Forfeit => String::from("forfeit"),
+            ViolationPolicy::Disqualify => String::from("disqualify"),
+        });
+        line("undo", String::from(match session.undo_policy() {
+            UndoPolicy::Restore => "restore",
+            UndoPolicy::Reroll => "reroll",
+        }));
+        let control = self.clock().control();
+        line("clock", format!("{} {}", write_millis(control.per_decision), write_millis(control.per_game)));
+        line("players", self.players().len().to_string());
+        for (seat, player) in self.players().iter().enumerate() {
+            let spec = player.spec().ok_or_else(|| SaveError::Player(player.name().to_string()))?;
+            line(&format!("player.{}", seat), spec);
+        }
+        for (seat, state) in session.states().iter().enumerate() {
+            let key = |name: &str| format!("seat.{}.{}", seat, name);
+            line(&key("clock"), self.clock().used(seat).as_millis().to_string());
+            line(&key("roll_bank"), state.roll_bank.to_string());
+            line(&key("disqualified"), state.disqualified.to_string());
+            line(&key("announcement"), match state.card.announcement() {
+                Some((column, category)) => notation::write_box(column, category),
+                None => String::from("-"),
+            });
+            let history: Vec<String> = state.history.iter().map(notation::write_turn).collect();
+            line(&key("history"), history.join(" / "));
+        }
+        let position = session.position();
+        line("turn", position.turn.to_string());
+        line("seat", position.seat.to_string());
+        line("tosses", notation::write_moves(&position.tosses, &position.keeps, &position.timeouts));
+        line("announce_timeout", position.timeouts.contains(&Timeout::Announce).to_string());
+        line("toss_num", position.toss_num.to_string());
+        line("turn_bank", position.turn_bank.to_string());
+        line("action", String::from(match position.action {
+            Action::Announce { .. } => "announce",
+            Action::Keep { .. } => "keep",
+            Action::Entry { .. } => "entry",
+            Action::Finished => "finished",
+        }));
+        Ok(out)
+    }
+
+    /// a game that continues exactly where the saved one stopped
+    pub fn load(text: &str) -> Saved<Game<SeededRng>> {
+        let mut lines = text.lines();
+        let header = lines.next().unwrap_or("");
+        if header != format!("{} {}", HEADER, VERSION) {
+            return Err(SaveError::Version(header.to_string()))
+        }
+        let fields = Fields::read(lines)?;
+        let rules = read_rules(&fields)?;
+        if fields.get("rng")? != GENERATOR {
+            return Err(fields.invalid("rng"))
+        }
+        let rng = SeededRng::resume(fields.parse("seed")?, fields.parse("draws")?);
+
+        let mut players: Vec<Box<dyn Player>> = Vec::new();
+        let mut states = Vec::new();
+        // the roll bank every seat has after its recorded turns
+        let mut banks = Vec::new();
+        for seat in 0..fields.parse::<usize>("players")? {
+            let spec = fields.get(&format!("player.{}", seat))?;
+            players.push(player::from_spec(spec).ok_or_else(|| SaveError::Player(spec.to_string()))?);
+            let key = |name: &str| format!("seat.{}.{}", seat, name);
+            let mut state = GameState::new(&rules);
+            let history = fields.get(&key("history"))?;
+            for turn in history.split(" / ").filter(|turn| !turn.is_empty()) {
+                let turn = notation::parse_turn(turn).ok_or_else(|| fields.invalid(&key("history")))?;
+                notation::check_turn(&state, &turn).map_err(|_| fields.invalid(&key("history")))?;
+                state.replay(turn);
+            }
+            banks.push(state.roll_bank);
+            state.roll_bank = fields.parse(&key("roll_bank"))?;
+            state.disqualified = fields.parse(&key("disqualified"))?;
+            let announcement = fields.get(&key("announcement"))?;
+            if announcement != "-" {
+                let (column, category) = notation::parse_box(announcement)
+                    .filter(|&(column, category)| state.card.can_announce(column, category))
+                    .ok_or_else(|| fields.invalid(&key("announcement")))?;
+                state.card.announce(column, category);
+            }
+            states.push(state);
+        }
+
+        let seat = fields.parse("seat")?;
+        let (tosses, keeps, mut timeouts) = notation::parse_moves(fields.get("tosses")?)
+            .ok_or_else(|| fields.invalid("tosses"))?;
+        if fields.parse("announce_timeout")? {
+            timeouts.insert(0, Timeout::Announce);
+        }
+        let toss = tosses.len() as u8;
+        let toss_num: u8 = fields.parse("toss_num")?;
+        let turn_bank: u8 = fields.parse("turn_bank")?;
+        let action = match fields.get("action")? {
+            "announce" => Action::Announce { seat },
+            "keep" => Action::Keep {
+                seat,
+                remaining_tosses: toss_num.checked_sub(toss).ok_or_else(|| fields.invalid("toss_num"))?,
+            },
+            "entry" => Action::Entry { seat },
+            "finished" => Action::Finished,
+            _ => return Err(fields.invalid("action")),
+        };
+        if action != Action::Finished && seat >= states.len() {
+            return Err(fields.invalid("seat"))
+        }
+        if action != Action::Finished
+            && (toss > toss_num || Some(toss_num) != rules.toss_num.checked_add(turn_bank)) {
+            return Err(fields.invalid("toss_num"))
+        }
+        let finished = action == Action::Finished;
+        let turn: usize = fields.parse("turn")?;
+        if turn > rules.turns() || (!finished && turn == rules.turns()) {
+            return Err(fields.invalid("turn"))
+        }
+        // the seats before this one have played the turn, a finished game
+        // before the last turn has been won by this seat
+        let won = finished && turn < rules.turns();
+        for (other, state) in states.iter().enumerate() {
+            let played = turn + (other < seat || (won && other == seat)) as usize;
+            if state.history.len() > played || (!state.disqualified && state.history.len() != played) {
+                return Err(fields.invalid("turn"))
+            }
+        }
+        if won && !states.get(seat).is_some_and(|state| state.card.has_won()) {
+            return Err(fields.invalid("turn"))
+        }
+        if !finished && states[seat].disqualified {
+            return Err(fields.invalid("seat"))
+        }
+        for (other, state) in states.iter().enumerate().filter(|(_, state)| !state.disqualified) {
+            // rolls are banked when the tossing stops
+            let bank = match action {
+                Action::Entry { seat } if seat == other && rules.save_rolls => toss_num - toss,
+                _ => banks[other],
+            };
+            if state.roll_bank != bank {
+                return Err(fields.invalid(&format!("seat.{}.roll_bank", other)))
+            }
+        }
+        if !finished && turn_bank != banks[seat] {
+            return Err(fields.invalid("turn_bank"))
+        }
+        let moves = match action {
+            Action::Announce { .. } => toss == 1 && keeps.is_empty(),
+            Action::Keep { .. } => keeps.len() < tosses.len(),
+            Action::Entry { .. } => toss == toss_num || keeps.len() == tosses.len(),
+            Action::Finished => true,
+        };
+        if !(moves && (finished || check_moves(&rules, &tosses, &keeps))) {
+            return Err(fields.invalid("tosses"))
+        }
+        if let Some(state) = states.get_mut(seat) {
+            state.card.set_toss(toss);
+        }
+        let position = Position {
+            turn,
+            seat,
+            dice: tosses[tosses.len() - 1].clone(),
+            toss,
+            toss_num,
+            tosses,
+            keeps,
+            timeouts,
+            action,
+            turn_bank,
+        };
+        let mut session = GameSession::resume(rules, states, rng, position);
+        session.set_undo_policy(match fields.get("undo")? {
+            "restore" => UndoPolicy::Restore,
+            "reroll" => UndoPolicy::Reroll,
+            _ => return Err(fields.invalid("undo")),
+        });
+        let mut game = Game::from_session(players, session);
+        let clock = fields.get("clock")?.split(' ').map(read_millis).collect::<Option<Vec<_>>>();
+        let mut clock = match clock.as_ref().map(|clock| &clock[..]) {
+            Some(&[per_decision, per_game]) => Clock::new(TimeControl { per_decision, per_game }, game.players().len()),
+            _ => return Err(fields.invalid("clock")),
+        };
+        for seat in 0..game.players().len() {
+            clock.set_used(seat, Duration::from_millis(fields.parse(&format!("seat.{}.clock", seat))?));
+        }
+        game.set_clock(clock);
+        let policy = fields.get("policy")?;
+        game.set_violation_policy(match policy {
+            "forfeit" => ViolationPolicy::Forfeit,
+            "disqualify" => ViolationPolicy::Disqualify,
+            _ => match policy.split(':').collect::<Vec<_>>()[..] {
+                ["reask", attempts] => ViolationPolicy::Reask(attempts.parse().map_err(|_| fields.invalid("policy"))?),
+                _ => return Err(fields.invalid("policy")),
+            },
+        });
+        Ok(game)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Saved<()> {
+        fs::write(path, self.save()?)?;
+        Ok(())
+    }
+
+    pub fn load_from(path: &Path) -> Saved<Game<SeededRng>> {
+        Game::load(&fs::read_to_string(path)?)
+    }
+}
+
+/// `key=value` lines
+pub(crate) struct Fields(HashMap<String, String>);
+
+impl Fields {
+    pub(crate) fn new(fields: HashMap<String, String>) -> Fields {
+        Fields(fields)
+    }
+
+    fn read<'a, I: Iterator<Item = &'a str>>(lines: I) -> Saved<Fields> {
+        let mut fields = HashMap::new();
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next()
+                .ok_or_else(|| SaveError::Invalid { key: key.to_string(), value: String::new() })?;
+            fields.insert(key.to_string(), value.trim().to_string());
+        }
+        Ok(Fields(fields))
+    }
+
+    fn get(&self, key: &str) -> Saved<&str> {
+        self.0.get(key).map(|value| value.as_str()).ok_or_else(|| SaveError::Missing(key.to_string()))
+    }
+
+    fn parse<T: FromStr>(&self, key: &str) -> Saved<T> {
+        self.get(key)?.parse().map_err(|_| self.invalid(key))
+    }
+
+    fn invalid(&self, key: &str) -> SaveError {
+        let value = self.0.get(key).cloned().unwrap_or_default();
+        SaveError::Invalid { key: key.to_string(), value }
+    }
+
+    fn list<T, F>(&self, key: &str, parse: F) -> Saved<Vec<T>>
+        where F: Fn(&str) -> Option<T> {
+        self.get(key)?.split_whitespace()
+            .map(|item| parse(item).ok_or_else(|| self.invalid(key)))
+            .collect()
+    }
+}
+
+/// milliseconds or `-` for no limit
+fn write_millis(time: Option<Duration>) -> String {
+    time.map_or_else(|| String::from("-"), |time| time.as_millis().to_string())
+}
+
+fn read_millis(text: &str) -> Option<Option<Duration>> {
+    match text {
+        "-" => Some(None),
+        _ => text.parse().ok().map(|millis| Some(Duration::from_millis(millis))),
+    }
+}
+
+fn join(dice: &[Die]) -> String {
+    dice.iter().map(|die| die.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn parse_dice(text: &str) -> Option<Dice> {
+    if text.is_empty() {
+        return Some(Dice::new())
+    }
+    text.split(',').map(|die| die.parse().ok()).collect()
+}
+
+/// the tosses and keeps of the turn so far follow each other by the rules
+fn check_moves(rules: &RuleSet, tosses: &[Dice], keeps: &[Dice]) -> bool {
+    let valid = |dice: &Dice| dice.len() == rules.dice_num as usize
+        && dice.iter().all(|die| rules.dice_faces.contains(die));
+    tosses.iter().all(valid) && keeps.iter().enumerate().all(|(i, keep)| {
+        is_subset(keep, &tosses[i]) && match tosses.get(i + 1) {
+            Some(next) => is_subset(keep, next),
+            // only keeping all dice ends the tossing early
+            None => keep == &tosses[i],
+        }
+    })
+}
+
+fn write_rules(out: &mut String, rules: &RuleSet) {
+    for (key, value) in rule_fields(rules) {
+        writeln!(out, "rules.{}={}", key, value).expect("writing to a String");
+    }
+}
+
+/// every number of the rules by the name of its field
+pub(crate) fn rule_fields(rules: &RuleSet) -> Vec<(&'static str, String)> {
+    let categories: Vec<String> = rules.categories.iter().map(|cat| format!("{:?}", cat)).collect();
+    let columns: Vec<String> = rules.columns.iter()
+        .map(|column| format!("{}:{:?}", column.weight, column.order))
+        .collect();
+    vec![
+        ("variant", format!("{:?}", rules.variant)),
+        ("categories", categories.join(" ")),
+        ("columns", columns.join(" ")),
+        ("dice_num", rules.dice_num.to_string()),
+        ("dice_faces", join(&rules.dice_faces)),
+        ("toss_num", rules.toss_num.to_string()),
+        ("bonus_reach", rules.bonus_reach.to_string()),
+        ("bonus_value", rules.bonus_value.to_string()),
+        ("full_house_value", rules.full_house_value.to_string()),
+        ("small_straight_value", rules.small_straight_value.to_string()),
+        ("large_straight_value", rules.large_straight_value.to_string()),
+        ("full_straight_value", rules.full_straight_value.to_string()),
+        ("poker_value", rules.poker_value.to_string()),
+        ("yahtzee_value", rules.yahtzee_value.to_string()),
+        ("double_yahtzee_value", rules.double_yahtzee_value.to_string()),
+        ("yahtzee_bonus_value", rules.yahtzee_bonus_value.to_string()),
+        ("served_bonus", rules.served_bonus.to_string()),
+        ("joker", rules.joker.to_string()),
+        ("save_rolls", rules.save_rolls.to_string()),
+    ]
+}
+
+/// the rules from `rules.` fields
+pub(crate) fn read_rules(fields: &Fields) -> Saved<RuleSet> {
+    let key = |name: &str| format!("rules.{}", name);
+    let variant = fields.get(&key("variant"))?;
+    Ok(RuleSet {
+        variant: Variant::by_name(variant).ok_or_else(|| fields.invalid(&key("variant")))?,
+        categories: fields.list(&key("categories"), Category::by_name)?,
+        columns: fields.list(&key("columns"), |column| {
+            let mut parts = column.splitn(2, ':');
+            let weight = parts.next()?.parse().ok()?;
+            Some(Column::new(weight, Order::by_name(parts.next()?)?))
+        })?,
+        dice_num: fields.parse(&key("dice_num"))?,
+        dice_faces: parse_dice(fields.get(&key("dice_faces"))?)
+            .ok_or_else(|| fields.invalid(&key("dice_faces")))?,
+        toss_num: fields.parse(&key("toss_num"))?,
+        bonus_reach: fields.parse(&key("bonus_reach"))?,
+        bonus_value: fields.parse(&key("bonus_value"))?,
+        full_house_value: fields.parse(&key("full_house_value"))?,
+        small_straight_value: fields.parse(&key("small_straight_value"))?,
+        large_straight_value: fields.parse(&key("large_straight_value"))?,
+        full_straight_value: fields.parse(&key("full_straight_value"))?,
+        poker_value: fields.parse(&key("poker_value"))?,
+        yahtzee_value: fields.parse(&key("yahtzee_value"))?,
+        double_yahtzee_value: fields.parse(&key("double_yahtzee_value"))?,
+        yahtzee_bonus_value: fields.parse(&key("yahtzee_bonus_value"))?,
+        served_bonus: fields.parse(&key("served_bonus"))?,
+        joker: fields.parse(&key("joker"))?,
+        save_rolls: fields.parse(&key("save_rolls"))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai::{Difficulty, YahtzeeAI};
+    use events::SilentSink;
+
+    fn game(rules: RuleSet, seed: u64) -> Game<SeededRng> {
+        let players: Vec<Box<dyn Player>> = vec![
+            Box::new(YahtzeeAI::with_difficulty("A", Difficulty::Easy)),
+            Box::new(YahtzeeAI::with_difficulty("B", Difficulty::Easy)),
+        ];
+        let mut game = Game::with_seed(players, rules, seed);
+        game.set_event_sink(Box::new(SilentSink));
+        game
+    }
+
+    #[test]
+    fn test_resume() {
+        for rules in [RuleSet::yahtzee(), RuleSet::kniffel(), RuleSet::maxi_yatzy()].iter() {
+            let mut original = game(rules.clone(), 11);
+            for _ in 0..25 {
+                original.play_decision();
+            }
+            let saved = original.save().unwrap();
+            let mut resumed = Game::load(&saved).unwrap();
+            resumed.set_event_sink(Box::new(SilentSink));
+            assert_eq!(resumed.save().unwrap(), saved);
+            assert_eq!(resumed.rules(), rules);
+            assert_eq!(original.run(), resumed.run());
+        }
+    }
+
+    #[test]
+    fn test_clock() {
+        let mut original = game(RuleSet::kniffel(), 4);
+        let control = TimeControl { per_decision: Some(Duration::from_millis(0)), per_game: None };
+        original.set_time_control(control);
+        for _ in 0..7 {
+            original.play_decision();
+        }
+        let saved = original.save().unwrap();
+        assert!(saved.contains("clock=0 -\n"));
+        assert!(saved.contains("announce_timeout=true\n"));
+        let mut resumed = Game::load(&saved).unwrap();
+        resumed.set_event_sink(Box::new(SilentSink));
+        assert_eq!(resumed.clock().control(), control);
+        assert_eq!(resumed.save().unwrap(), saved);
+        assert_eq!(original.run(), resumed.run());
+    }
+
+    #[test]
+    fn test_seeded_rng() {
+        let mut rng = SeededRng::new(3);
+        for _ in 0..10 {
+            rng.next_u32();
+        }
+        let mut resumed = SeededRng::resume(rng.seed(), rng.draws());
+        assert_eq!(rng.next_u64(), resumed.next_u64());
+        // pinned, a save must give the same dice everywhere
+        assert_eq!(SeededRng::new(42).next_u64(), 3238969106371667296);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(Game::load("yahtzee-save 99\n"), Err(SaveError::Version(_))));
+        let saved = game(RuleSet::yahtzee(), 1).save().unwrap();
+        let broken = saved.replace("rules.toss_num=3", "rules.toss_num=three");
+        assert!(matches!(Game::load(&broken), Err(SaveError::Invalid { ref key, .. }) if key == "rules.toss_num"));
+        assert!(invalid(&saved.replace("rng=xorshift", "rng=small"), "rng"));
+        let missing = saved.replace("turn=0\n", "");
+        assert!(matches!(Game::load(&missing), Err(SaveError::Missing(ref key)) if key == "turn"));
+    }
+
+    /// the save with the value of the key replaced
+    fn set(saved: &str, key: &str, value: &str) -> String {
+        saved.lines()
+            .map(|line| if line.starts_with(&format!("{}=", key)) { format!("{}={}\n", key, value) } else { format!("{}\n", line) })
+            .collect()
+    }
+
+    fn invalid(saved: &str, key: &str) -> bool {
+        matches!(Game::load(saved), Err(SaveError::Invalid { key: ref invalid, .. }) if invalid == key)
+    }
+
+    #[test]
+    fn test_illegal_position() {
+        let saved = game(RuleSet::kniffel(), 1).save().unwrap();
+        assert!(invalid(&set(&saved, "seat.0.history", "11111 Ones@9=1"), "seat.0.history"));
+        assert!(invalid(&set(&saved, "seat.0.history", "11111 Ones@3=5 / 11111 Ones@3=5"), "seat.0.history"));
+        assert!(invalid(&set(&saved, "seat.0.history", "1111 Ones@3=4"), "seat.0.history"));
+        assert!(invalid(&set(&saved, "seat.0.announcement", "Ones@9"), "seat.0.announcement"));
+        assert!(invalid(&set(&saved, "seat.0.announcement", "Ones"), "seat.0.announcement"));
+
+        let saved = game(RuleSet::yahtzee(), 1).save().unwrap();
+        let keep = set(&set(&saved, "action", "keep"), "tosses", "11366 k11 11234 k11 11456");
+        assert!(invalid(&set(&keep, "toss_num", "2"), "toss_num"));
+        assert!(invalid(&set(&saved, "toss_num", "5"), "toss_num"));
+    }
+
+    #[test]
+    fn test_position_against_history() {
+        let mut original = game(RuleSet::maxi_yatzy(), 5);
+        while original.play_decision() {
+            let saved = original.save().unwrap();
+            assert_eq!(Game::load(&saved).unwrap().save().unwrap(), saved);
+        }
+        let saved = original.save().unwrap();
+        assert_eq!(Game::load(&saved).unwrap().save().unwrap(), saved);
+
+        let saved = game(RuleSet::yahtzee(), 1).save().unwrap();
+        assert!(invalid(&set(&saved, "turn", "1"), "turn"));
+        assert!(invalid(&set(&saved, "turn", "13"), "turn"));
+        assert!(invalid(&set(&saved, "seat", "1"), "turn"));
+        assert!(invalid(&set(&saved, "seat.1.history", "11111 k11111 Ones=5"), "turn"));
+        assert!(invalid(&set(&saved, "seat.0.roll_bank", "2"), "seat.0.roll_bank"));
+        assert!(invalid(&set(&set(&saved, "toss_num", "5"), "turn_bank", "2"), "turn_bank"));
+        assert!(invalid(&set(&saved, "tosses", "1111"), "tosses"));
+        assert!(invalid(&set(&saved, "tosses", "11366 k11 23456"), "tosses"));
+        assert!(invalid(&set(&saved, "tosses", "11366 k22 22456"), "tosses"));
+        assert!(invalid(&set(&set(&saved, "action", "entry"), "tosses", "11366 k11 11456"), "tosses"));
+        assert!(Game::load(&set(&set(&saved, "action", "entry"), "tosses", "11366 k11366")).is_ok());
+    }
+}
@@ -0,0 +1,268 @@
+use types::{Category, ColumnEntry, DiceCategory, Value};
+use types::Category::*;
+use dice::Dice;
+use result::Result;
+use rules::{Order, RuleSet, Variant};
+
+/// All columns of one player, each scored like a single Yahtzee card and
+/// weighted by the multiplier of its column.
+#[derive(Clone)]
+pub struct Scorecard {
+    rules: RuleSet,
+    columns: Vec<Result>,
+    announcement: Option<(usize, Category)>,
+    /// toss of the turn that produced the current dice
+    toss: u8,
+    won: bool,
+}
+
+impl Scorecard {
+    pub fn new(rules: &RuleSet) -> Scorecard {
+        let columns = rules.columns.iter().map(|_| Result::new(rules)).collect();
+        Scorecard { rules: rules.clone(), columns, announcement: None, toss: 0, won: false }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn columns(&self) -> &[Result] {
+        &self.columns
+    }
+
+    pub fn column(&self, column: usize) -> &Result {
+        &self.columns[column]
+    }
+
+    pub fn weight(&self, column: usize) -> u32 {
+        self.rules.columns[column].weight
+    }
+
+    pub fn order(&self, column: usize) -> Order {
+        self.rules.columns[column].order
+    }
+
+    pub fn value(&self) -> u32 {
+        self.columns.iter().enumerate()
+            .map(|(i, result)| self.weight(i) * result.value())
+            .sum()
+    }
+
+    pub fn has(&self, column: usize, category: Category) -> bool {
+        self.columns.get(column).is_some_and(|result| result.has(category))
+    }
+
+    /// the only box of an ordered column that may be filled next
+    pub fn next_in_order(&self, column: usize) -> Option<Category> {
+        let result = self.columns.get(column)?;
+        let mut open = self.rules.categories.iter().filter(|&&cat| !result.has(cat));
+        match self.order(column) {
+            Order::Down => open.next().cloned(),
+            Order::Up => open.next_back().cloned(),
+            Order::Free | Order::Announce => None,
+        }
+    }
+
+    pub fn can_write(&self, dice: &Dice, column: usize, category: Category) -> bool {
+        let result = match self.columns.get(column) {
+            Some(result) => result,
+            None => return false,
+        };
+        if let Some(announced) = self.announcement {
+            return announced == (column, category) && !result.has(category)
+        }
+        match self.order(column) {
+            Order::Free => result.can_write(dice, category),
+            Order::Down | Order::Up => self.next_in_order(column) == Some(category),
+            Order::Announce => false,
+        }
+    }
+
+    pub fn entry_of_category(&self, dice: &Dice, column: usize, category: Category) -> ColumnEntry {
+        let (category, mut value) = self.columns[column].entry_of_category(dice, category);
+        if self.served() && value > 0 && [LargeStraght, FullHouse, FourOfAKind].contains(&category) {
+            value += self.rules.served_bonus;
+        }
+        (column, (category, value))
+    }
+
+    pub fn possible_entries(&self, dice: &Dice) -> Vec<ColumnEntry> {
+        let mut entries = Vec::new();
+        for column in 0..self.columns.len() {
+            for &cat in self.rules.categories.iter() {
+                if self.can_write(dice, column, cat) {
+                    entries.push(self.entry_of_category(dice, column, cat));
+                }
+            }
+        }
+        entries
+    }
+
+    pub fn announcement(&self) -> Option<(usize, Category)> {
+        self.announcement
+    }
+
+    pub fn can_announce(&self, column: usize, category: Category) -> bool {
+        self.announcement.is_none()
+            && column < self.columns.len()
+            && self.order(column) == Order::Announce
+            && self.rules.categories.contains(&category)
+            && !self.has(column, category)
+    }
+
+    /// all open boxes are in announce columns, so the player has to announce
+    pub fn must_announce(&self) -> bool {
+        self.announcement.is_none() && self.open() > 0
+            && (0..self.columns.len())
+                .filter(|&i| self.order(i) != Order::Announce)
+                .all(|i| self.rules.categories.iter().all(|&cat| self.has(i, cat)))
+    }
+
+    /// the boxes that could be announced
+    pub fn announceable(&self) -> Vec<(usize, Category)> {
+        let mut boxes = Vec::new();
+        for column in 0..self.columns.len() {
+            for &cat in self.rules.categories.iter() {
+                if self.can_announce(column, cat) {
+                    boxes.push((column, cat));
+                }
+            }
+        }
+        boxes
+    }
+
+    pub fn announce(&mut self, column: usize, category: Category) {
+        self.announcement = Some((column, category));
+    }
+
+    pub fn clear_announcement(&mut self) {
+        self.announcement = None;
+    }
+
+    pub fn toss(&self) -> u8 {
+        self.toss
+    }
+
+    pub fn set_toss(&mut self, toss: u8) {
+        self.toss = toss;
+    }
+
+    /// the current dice were made with the first toss of the turn
+    pub fn served(&self) -> bool {
+        self.toss == 1
+    }
+
+    /// a Generala with the first toss wins the game
+    pub fn wins_instantly(&self, dice: &Dice) -> bool {
+        self.rules.variant == Variant::Generala && self.served() && dice.is_category(&self.rules, &Yahtzee)
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.won
+    }
+
+    pub fn earns_yahtzee_bonus(&self, dice: &Dice, column: usize) -> bool {
+        self.columns[column].earns_yahtzee_bonus(dice)
+    }
+
+    pub fn add(&mut self, dice: &Dice, entry: ColumnEntry) {
+        if self.wins_instantly(dice) {
+            self.won = true;
+        }
+        let (column, entry) = entry;
+        self.columns[column].add(dice, entry);
+        self.announcement = None;
+    }
+
+    /// takes back the last `add` of the dice, an instant win with it included
+    pub fn remove(&mut self, dice: &Dice, column: usize, category: Category) -> Option<Value> {
+        let value = self.columns.get_mut(column)?.remove(dice, category)?;
+        self.won = false;
+        Some(value)
+    }
+
+    /// number of boxes left to fill
+    pub fn open(&self) -> usize {
+        self.columns.iter()
+            .map(|result| self.rules.categories.iter().filter(|&&cat| !result.has(cat)).count())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triple_weights() {
+        let rules = RuleSet::triple_yahtzee();
+        let mut card = Scorecard::new(&rules);
+        let dice = vec![1, 2, 3, 4, 5];
+        card.add(&dice, (0, (Chance, 15)));
+        card.add(&dice, (2, (Chance, 15)));
+        assert_eq!(card.value(), 15 + 3 * 15);
+        assert!(card.can_write(&dice, 1, Chance));
+        assert!(!card.can_write(&dice, 2, Chance));
+        assert!(!card.can_write(&dice, 3, Chance));
+        assert_eq!(card.open(), 3 * 13 - 2);
+    }
+
+    #[test]
+    fn test_ordered_columns() {
+        let rules = RuleSet::kniffel();
+        let mut card = Scorecard::new(&rules);
+        let dice = vec![1, 1, 3, 4, 5];
+        assert!(card.can_write(&dice, 0, Ones));
+        assert!(!card.can_write(&dice, 0, Twos));
+        assert!(card.can_write(&dice, 1, Chance));
+        assert!(!card.can_write(&dice, 1, Yahtzee));
+        assert!(card.can_write(&dice, 2, SmallStraight));
+        assert!(!card.can_write(&dice, 3, SmallStraight));
+        card.add(&dice, (0, (Ones, 2)));
+        assert_eq!(card.next_in_order(0), Some(Twos));
+        assert!(card.can_write(&dice, 0, Twos));
+    }
+
+    #[test]
+    fn test_announce() {
+        let rules = RuleSet::kniffel();
+        let mut card = Scorecard::new(&rules);
+        let dice = vec![2, 2, 2, 5, 5];
+        assert!(!card.can_announce(0, FullHouse));
+        assert!(card.can_announce(3, FullHouse));
+        assert!(!card.must_announce());
+        card.announce(3, FullHouse);
+        assert_eq!(card.possible_entries(&dice), vec![(3, (FullHouse, 25))]);
+        card.add(&dice, (3, (FullHouse, 25)));
+        assert_eq!(card.announcement(), None);
+        assert!(!card.can_write(&dice, 3, Chance));
+    }
+
+    #[test]
+    fn test_generala_served() {
+        let rules = RuleSet::generala();
+        let mut card = Scorecard::new(&rules);
+        let full = vec![3, 3, 3, 5, 5];
+        card.set_toss(2);
+        assert_eq!(card.entry_of_category(&full, 0, FullHouse), (0, (FullHouse, 30)));
+        card.set_toss(1);
+        assert_eq!(card.entry_of_category(&full, 0, FullHouse), (0, (FullHouse, 35)));
+        assert_eq!(card.entry_of_category(&full, 0, FourOfAKind), (0, (FourOfAKind, 0)));
+        assert_eq!(card.entry_of_category(&full, 0, Threes), (0, (Threes, 9)));
+        let generala = vec![4, 4, 4, 4, 4];
+        assert_eq!(card.entry_of_category(&generala, 0, DoubleYahtzee), (0, (DoubleYahtzee, 0)));
+        card.add(&generala, (0, (Yahtzee, 50)));
+        assert!(card.has_won());
+    }
+
+    #[test]
+    fn test_double_generala() {
+        let rules = RuleSet::generala();
+        let mut card = Scorecard::new(&rules);
+        let generala = vec![4, 4, 4, 4, 4];
+        card.set_toss(3);
+        card.add(&generala, (0, (Yahtzee, 50)));
+        assert!(!card.has_won());
+        assert_eq!(card.entry_of_category(&generala, 0, DoubleYahtzee), (0, (DoubleYahtzee, 100)));
+    }
+}
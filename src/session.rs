@@ -0,0 +1,847 @@
+use dice::{Dice, is_subset};
+use events::GameEvent;
+use game::{GameState, Timeout, Turn, toss};
+use rules::{Order, RuleSet};
+use scorecard::Scorecard;
+use table::TableView;
+use types::{Category, ColumnEntry, Value};
+use rand::Rng;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+/// A decision of a player that is not allowed by the rules.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuleViolation {
+    /// kept dice that are not part of the toss
+    InvalidKeep { dice: Dice, keep: Dice },
+    /// box that is filled, out of order or not allowed for the dice
+    IllegalBox { column: usize, category: Category },
+    /// value that differs from what the dice score
+    WrongValue { entry: ColumnEntry, expected: Value },
+    IllegalAnnouncement { column: usize, category: Category },
+    MissingAnnouncement,
+    /// decision that is not the pending action
+    OutOfTurn { expected: Action },
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleViolation::InvalidKeep { dice, keep } =>
+                write!(f, "can not keep {:?} from {:?}", keep, dice),
+            RuleViolation::IllegalBox { column, category } =>
+                write!(f, "can not write to {:?} in column {}", category, column + 1),
+            RuleViolation::WrongValue { entry: (_, (category, value)), expected } =>
+                write!(f, "{:?} scores {} not {}", category, expected, value),
+            RuleViolation::IllegalAnnouncement { column, category } =>
+                write!(f, "can not announce {:?} in column {}", category, column + 1),
+            RuleViolation::MissingAnnouncement =>
+                write!(f, "has to announce a box"),
+            RuleViolation::OutOfTurn { expected } =>
+                write!(f, "acted out of turn, expected {:?}", expected),
+        }
+    }
+}
+
+impl Error for RuleViolation {}
+
+pub type Checked<T> = ::std::result::Result<T, RuleViolation>;
+
+pub fn check_keep(dice: &Dice, keep: &Dice) -> Checked<()> {
+    if is_subset(keep, dice) {
+        Ok(())
+    } else {
+        Err(RuleViolation::InvalidKeep { dice: dice.clone(), keep: keep.clone() })
+    }
+}
+
+pub fn check_entry(card: &Scorecard, dice: &Dice, entry: ColumnEntry) -> Checked<()> {
+    let (column, (category, _)) = entry;
+    if !card.can_write(dice, column, category) {
+        return Err(RuleViolation::IllegalBox { column, category })
+    }
+    let (_, (_, expected)) = card.entry_of_category(dice, column, category);
+    if entry != (column, (category, expected)) {
+        return Err(RuleViolation::WrongValue { entry, expected })
+    }
+    Ok(())
+}
+
+pub fn check_announcement(card: &Scorecard, announcement: Option<(usize, Category)>) -> Checked<()> {
+    match announcement {
+        Some((column, category)) if !card.can_announce(column, category) =>
+            Err(RuleViolation::IllegalAnnouncement { column, category }),
+        None if card.must_announce() => Err(RuleViolation::MissingAnnouncement),
+        _ => Ok(()),
+    }
+}
+
+/// the announcement of a player out of time, the first box if one is required
+pub fn timeout_announcement(card: &Scorecard) -> Option<(usize, Category)> {
+    if card.must_announce() { card.announceable().first().cloned() } else { None }
+}
+
+/// the entry of a player out of time, the one worth the most in the leftmost column
+pub fn timeout_entry(card: &Scorecard, dice: &Dice) -> Option<ColumnEntry> {
+    card.possible_entries(dice).into_iter()
+        .max_by_key(|&(column, (_, value))| (value, Reverse(column)))
+}
+
+/// The decision the game is waiting for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// after the first toss when the rules have an announce column
+    Announce { seat: usize },
+    Keep { seat: usize, remaining_tosses: u8 },
+    Entry { seat: usize },
+    Finished,
+}
+
+/// Where a session stands, enough to resume it with the same states.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Position {
+    pub turn: usize,
+    pub seat: usize,
+    pub dice: Dice,
+    pub toss: u8,
+    pub toss_num: u8,
+    /// the dice after every toss of the turn so far
+    pub tosses: Vec<Dice>,
+    /// the dice kept in the turn so far
+    pub keeps: Vec<Dice>,
+    /// decisions of the turn so far that the clock made
+    pub timeouts: Vec<Timeout>,
+    pub action: Action,
+    /// the roll bank of the seat when the turn started
+    pub turn_bank: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Phase {
+    Announce,
+    Keep,
+    Entry,
+    Finished,
+}
+
+/// What `GameSession::undo` does with the dice.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UndoPolicy {
+    /// the dice are put back as they were before the decision
+    #[default]
+    Restore,
+    /// the turn of the decision starts over with a new toss
+    Reroll,
+}
+
+/// A decision that can be taken back and made again.
+#[derive(Clone, Debug, PartialEq)]
+enum Move {
+    Announce(Option<(usize, Category)>),
+    /// the dice after the keep, so a redo does not roll again
+    Keep { keep: Dice, dice: Dice },
+    Entry(ColumnEntry),
+}
+
+/// the turn as it was before a decision
+#[derive(Clone, Debug)]
+struct Snapshot {
+    turn: usize,
+    seat: usize,
+    dice: Dice,
+    toss: u8,
+    toss_num: u8,
+    tosses: Vec<Dice>,
+    keeps: Vec<Dice>,
+    timeouts: Vec<Timeout>,
+    phase: Phase,
+    roll_bank: u8,
+    turn_bank: u8,
+    announcement: Option<(usize, Category)>,
+}
+
+/// A game as an explicit state machine: the caller asks for the pending
+/// action and applies the decision of the seat whose turn it is.
+pub struct GameSession<R: Rng> {
+    rules: RuleSet,
+    states: Vec<GameState>,
+    rng: R,
+    turn: usize,
+    seat: usize,
+    dice: Dice,
+    /// tosses made in this turn
+    toss: u8,
+    /// tosses available in this turn including the saved ones
+    toss_num: u8,
+    /// the dice after every toss of this turn
+    tosses: Vec<Dice>,
+    /// the dice kept in this turn
+    keeps: Vec<Dice>,
+    /// the decisions of this turn made by the clock
+    timeouts: Vec<Timeout>,
+    phase: Phase,
+    /// the roll bank of the seat when the turn started
+    turn_bank: u8,
+    /// happened since the last `take_events`
+    events: Vec<GameEvent>,
+    undo_policy: UndoPolicy,
+    undo: Vec<(Snapshot, Move)>,
+    redo: Vec<Move>,
+    /// dice to toss for every seat before the rng is used
+    script: Vec<VecDeque<Dice>>,
+}
+
+impl<R: Rng> GameSession<R> {
+    pub fn new(rules: RuleSet, seats: usize, rng: R) -> GameSession<R> {
+        GameSession::scripted(rules, vec![Vec::new(); seats], rng)
+    }
+
+    /// a session whose tosses are given for every seat, the rng only
+    /// tosses when the dice of a seat run out
+    pub fn scripted(rules: RuleSet, script: Vec<Vec<Dice>>, rng: R) -> GameSession<R> {
+        let states = script.iter().map(|_| GameState::new(&rules)).collect();
+        let mut session = GameSession {
+            rules, states, rng,
+            turn: 0,
+            seat: 0,
+            dice: Dice::new(),
+            toss: 0,
+            toss_num: 0,
+            tosses: Vec::new(),
+            keeps: Vec::new(),
+            timeouts: Vec::new(),
+            phase: Phase::Finished,
+            turn_bank: 0,
+            events: Vec::new(),
+            undo_policy: UndoPolicy::default(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            script: script.into_iter().map(VecDeque::from).collect(),
+        };
+        session.start_turn();
+        session
+    }
+
+    /// a session that continues at the position, the decisions before can not be undone
+    pub fn resume(rules: RuleSet, states: Vec<GameState>, rng: R, position: Position) -> GameSession<R> {
+        let phase = match position.action {
+            Action::Announce { .. } => Phase::Announce,
+            Action::Keep { .. } => Phase::Keep,
+            Action::Entry { .. } => Phase::Entry,
+            Action::Finished => Phase::Finished,
+        };
+        GameSession {
+            rules, states, rng, phase,
+            turn: position.turn,
+            seat: position.seat,
+            dice: position.dice,
+            toss: position.toss,
+            toss_num: position.toss_num,
+            tosses: position.tosses,
+            keeps: position.keeps,
+            timeouts: position.timeouts,
+            turn_bank: position.turn_bank,
+            events: Vec::new(),
+            undo_policy: UndoPolicy::default(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            script: Vec::new(),
+        }
+    }
+
+    pub fn position(&self) -> Position {
+        Position {
+            turn: self.turn,
+            seat: self.seat,
+            dice: self.dice.clone(),
+            toss: self.toss,
+            toss_num: self.toss_num,
+            tosses: self.tosses.clone(),
+            keeps: self.keeps.clone(),
+            timeouts: self.timeouts.clone(),
+            action: self.pending_action(),
+            turn_bank: self.turn_bank,
+        }
+    }
+
+    pub fn pending_action(&self) -> Action {
+        let seat = self.seat;
+        match self.phase {
+            Phase::Announce => Action::Announce { seat },
+            Phase::Keep => Action::Keep { seat, remaining_tosses: self.remaining_tosses() },
+            Phase::Entry => Action::Entry { seat },
+            Phase::Finished => Action::Finished,
+        }
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn rng(&self) -> &R {
+        &self.rng
+    }
+
+    pub fn states(&self) -> &[GameState] {
+        &self.states
+    }
+
+    pub fn state(&self, seat: usize) -> &GameState {
+        &self.states[seat]
+    }
+
+    /// scorecard of the seat whose turn it is
+    pub fn card(&self) -> &Scorecard {
+        &self.states[self.seat].card
+    }
+
+    /// the table as the seat whose turn it is sees it
+    pub fn view<'a>(&'a self, names: &'a [String]) -> TableView<'a> {
+        TableView::new(&self.states, names, self.seat, self.turn)
+    }
+
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn seat(&self) -> usize {
+        self.seat
+    }
+
+    pub fn dice(&self) -> &Dice {
+        &self.dice
+    }
+
+    pub fn toss(&self) -> u8 {
+        self.toss
+    }
+
+    pub fn remaining_tosses(&self) -> u8 {
+        self.toss_num - self.toss
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.phase == Phase::Finished
+    }
+
+    /// the events of all actions applied since the last call
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        ::std::mem::take(&mut self.events)
+    }
+
+    pub fn apply_announce(&mut self, announcement: Option<(usize, Category)>) -> Checked<()> {
+        self.announce(announcement)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// rerolls all dice that are not kept, keeping all of them ends the tossing
+    pub fn apply_keep(&mut self, keep: Dice) -> Checked<()> {
+        self.keep(keep, None)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    pub fn apply_entry(&mut self, entry: ColumnEntry) -> Checked<()> {
+        self.entry(entry, false)?;
+        self.redo.clear();
+        Ok(())
+    }
+
+    /// makes the pending decision for a player who ran out of time: no
+    /// announcement unless one is required, all dice are kept and the entry
+    /// is the one worth the most
+    pub fn time_out(&mut self) -> Checked<()> {
+        if self.phase == Phase::Finished {
+            return Err(RuleViolation::OutOfTurn { expected: Action::Finished })
+        }
+        let seat = self.seat;
+        self.events.push(GameEvent::TimedOut { seat });
+        let card = &self.states[seat].card;
+        match self.phase {
+            Phase::Announce => {
+                let announcement = timeout_announcement(card);
+                self.announce(announcement)?;
+                self.timeouts.push(Timeout::Announce);
+            }
+            Phase::Keep => {
+                let toss = self.keeps.len();
+                let dice = self.dice.clone();
+                self.keep(dice, None)?;
+                self.timeouts.push(Timeout::Keep(toss));
+            }
+            Phase::Entry => {
+                match timeout_entry(card, &self.dice) {
+                    Some(entry) => self.entry(entry, true)?,
+                    None => self.forfeit(),
+                }
+            }
+            Phase::Finished => unreachable!(),
+        }
+        self.redo.clear();
+        Ok(())
+    }
+
+    pub fn undo_policy(&self) -> UndoPolicy {
+        self.undo_policy
+    }
+
+    pub fn set_undo_policy(&mut self, policy: UndoPolicy) {
+        self.undo_policy = policy;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// takes back the last decision, false if there is none
+    pub fn undo(&mut self) -> bool {
+        let (snapshot, last) = match self.undo.pop() {
+            Some(undo) => undo,
+            None => return false,
+        };
+        if let Move::Entry(_) = last {
+            self.states[snapshot.seat].undo_turn();
+        }
+        self.restore(&snapshot);
+        self.events.push(GameEvent::Undone { seat: self.seat });
+        match self.undo_policy {
+            UndoPolicy::Restore => self.redo.push(last),
+            UndoPolicy::Reroll => {
+                // the earlier decisions of the turn are taken back as well
+                while self.undo.last().is_some_and(|(s, _)| (s.turn, s.seat) == (snapshot.turn, snapshot.seat)) {
+                    if let Some((snapshot, _)) = self.undo.pop() {
+                        self.restore(&snapshot);
+                    }
+                }
+                self.redo.clear();
+                self.states[self.seat].roll_bank = self.turn_bank;
+                self.start_turn();
+            }
+        }
+        true
+    }
+
+    /// makes the last undone decision again, false if there is none
+    pub fn redo(&mut self) -> bool {
+        let next = match self.redo.pop() {
+            Some(next) => next,
+            None => return false,
+        };
+        let seat = self.seat;
+        let redone = match next {
+            Move::Announce(announcement) => self.announce(announcement),
+            Move::Keep { keep, dice } => self.keep(keep, Some(dice)),
+            Move::Entry(entry) => self.entry(entry, false),
+        };
+        if redone.is_ok() {
+            self.events.push(GameEvent::Redone { seat });
+        }
+        redone.is_ok()
+    }
+
+    /// the seat whose turn it is scores zero for this turn
+    pub fn forfeit(&mut self) {
+        if self.phase == Phase::Finished {
+            return
+        }
+        if self.phase != Phase::Entry {
+            self.stop_rolling();
+        }
+        let snapshot = self.snapshot();
+        self.events.push(GameEvent::Forfeited { seat: self.seat });
+        if let Some(entry) = self.states[self.seat].forfeit_entry(&self.dice) {
+            self.write_entry(entry, true);
+            self.undo.push((snapshot, Move::Entry(entry)));
+        }
+        self.redo.clear();
+        self.end_turn();
+    }
+
+    /// the seat whose turn it is takes no further turns
+    pub fn disqualify(&mut self) {
+        if self.phase == Phase::Finished {
+            return
+        }
+        self.states[self.seat].disqualified = true;
+        self.events.push(GameEvent::Disqualified { seat: self.seat });
+        self.undo.clear();
+        self.redo.clear();
+        self.next_seat();
+        self.start_turn();
+    }
+
+    fn expect(&self, phase: Phase) -> Checked<()> {
+        if self.phase == phase {
+            Ok(())
+        } else {
+            Err(RuleViolation::OutOfTurn { expected: self.pending_action() })
+        }
+    }
+
+    fn announce(&mut self, announcement: Option<(usize, Category)>) -> Checked<()> {
+        self.expect(Phase::Announce)?;
+        check_announcement(self.card(), announcement)?;
+        let snapshot = self.snapshot();
+        if let Some((column, category)) = announcement {
+            self.states[self.seat].card.announce(column, category);
+            self.events.push(GameEvent::Announced { seat: self.seat, column, category });
+        }
+        self.phase = self.rolling_phase();
+        self.undo.push((snapshot, Move::Announce(announcement)));
+        Ok(())
+    }
+
+    /// `tossed` are the dice after the keep if they are known already
+    fn keep(&mut self, mut keep: Dice, tossed: Option<Dice>) -> Checked<()> {
+        self.expect(Phase::Keep)?;
+        keep.sort_unstable();
+        check_keep(&self.dice, &keep)?;
+        let snapshot = self.snapshot();
+        self.events.push(GameEvent::Keep { seat: self.seat, keep: keep.clone() });
+        self.keeps.push(keep.clone());
+        if keep.len() == self.dice.len() {
+            self.stop_rolling();
+        } else {
+            self.dice = match tossed {
+                Some(dice) => dice,
+                None => self.roll(&keep),
+            };
+            self.toss += 1;
+            self.tosses.push(self.dice.clone());
+            self.states[self.seat].card.set_toss(self.toss);
+            self.events.push(GameEvent::Toss { seat: self.seat, toss: self.toss, dice: self.dice.clone() });
+            self.phase = self.rolling_phase();
+            if self.phase == Phase::Entry {
+                self.stop_rolling();
+            }
+        }
+        self.undo.push((snapshot, Move::Keep { keep, dice: self.dice.clone() }));
+        Ok(())
+    }
+
+    /// `timed_out` if the clock made the entry
+    fn entry(&mut self, entry: ColumnEntry, timed_out: bool) -> Checked<()> {
+        self.expect(Phase::Entry)?;
+        check_entry(self.card(), &self.dice, entry)?;
+        let snapshot = self.snapshot();
+        if timed_out {
+            self.timeouts.push(Timeout::Entry);
+        }
+        self.write_entry(entry, false);
+        self.undo.push((snapshot, Move::Entry(entry)));
+        self.end_turn();
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let state = &self.states[self.seat];
+        Snapshot {
+            turn: self.turn,
+            seat: self.seat,
+            dice: self.dice.clone(),
+            toss: self.toss,
+            toss_num: self.toss_num,
+            tosses: self.tosses.clone(),
+            keeps: self.keeps.clone(),
+            timeouts: self.timeouts.clone(),
+            phase: self.phase,
+            roll_bank: state.roll_bank,
+            turn_bank: self.turn_bank,
+            announcement: state.card.announcement(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.turn = snapshot.turn;
+        self.seat = snapshot.seat;
+        self.dice = snapshot.dice.clone();
+        self.toss = snapshot.toss;
+        self.toss_num = snapshot.toss_num;
+        self.tosses = snapshot.tosses.clone();
+        self.keeps = snapshot.keeps.clone();
+        self.timeouts = snapshot.timeouts.clone();
+        self.phase = snapshot.phase;
+        self.turn_bank = snapshot.turn_bank;
+        let state = &mut self.states[snapshot.seat];
+        state.roll_bank = snapshot.roll_bank;
+        state.card.set_toss(snapshot.toss);
+        match snapshot.announcement {
+            Some((column, category)) => state.card.announce(column, category),
+            None => state.card.clear_announcement(),
+        }
+    }
+
+    fn write_entry(&mut self, entry: ColumnEntry, forfeited: bool) {
+        let seat = self.seat;
+        let column = entry.0;
+        let card = &self.states[seat].card;
+        let yahtzee_bonus = card.earns_yahtzee_bonus(&self.dice, column);
+        let had_bonus = card.column(column).has_bonus();
+        let turn = Turn {
+            announcement: card.announcement(),
+            tosses: self.tosses.clone(),
+            keeps: self.keeps.clone(),
+            entry,
+            forfeited,
+            timeouts: self.timeouts.clone(),
+        };
+        self.states[seat].write_turn(turn);
+        self.events.push(GameEvent::EntryWritten { seat, entry });
+        if yahtzee_bonus {
+            self.events.push(GameEvent::YahtzeeBonus { seat, column });
+        }
+        let card = &self.states[seat].card;
+        if !had_bonus && card.column(column).has_bonus() {
+            self.events.push(GameEvent::BonusReached { seat, column });
+        }
+        if card.has_won() {
+            self.events.push(GameEvent::InstantWin { seat });
+        }
+    }
+
+    fn rolling_phase(&self) -> Phase {
+        if self.toss < self.toss_num {
+            Phase::Keep
+        } else {
+            Phase::Entry
+        }
+    }
+
+    fn stop_rolling(&mut self) {
+        if self.rules.save_rolls {
+            self.states[self.seat].roll_bank = self.toss_num - self.toss;
+        }
+        self.phase = Phase::Entry;
+    }
+
+    fn start_turn(&mut self) {
+        while self.turn < self.rules.turns() && self.states[self.seat].disqualified {
+            self.next_seat();
+        }
+        if self.turn >= self.rules.turns() || self.states.is_empty() {
+            self.phase = Phase::Finished;
+            return
+        }
+        self.dice = self.roll(&Dice::new());
+        let state = &mut self.states[self.seat];
+        self.turn_bank = state.roll_bank;
+        self.toss_num = self.rules.toss_num + state.roll_bank;
+        self.toss = 1;
+        self.tosses = vec![self.dice.clone()];
+        self.keeps = Vec::new();
+        self.timeouts = Vec::new();
+        state.card.set_toss(1);
+        self.events.push(GameEvent::TurnStarted { turn: self.turn, seat: self.seat });
+        self.events.push(GameEvent::Toss { seat: self.seat, toss: 1, dice: self.dice.clone() });
+        let announce = self.rules.columns.iter().any(|column| column.order == Order::Announce);
+        self.phase = if announce { Phase::Announce } else { self.rolling_phase() };
+        if self.phase == Phase::Entry {
+            self.stop_rolling();
+        }
+    }
+
+    fn roll(&mut self, keep: &Dice) -> Dice {
+        match self.script.get_mut(self.seat).and_then(|tosses| tosses.pop_front()) {
+            Some(dice) => dice,
+            None => toss(&self.rules, &mut self.rng, keep),
+        }
+    }
+
+    fn end_turn(&mut self) {
+        if self.states[self.seat].card.has_won() {
+            self.phase = Phase::Finished;
+            return
+        }
+        self.next_seat();
+        self.start_turn();
+    }
+
+    fn next_seat(&mut self) {
+        self.seat += 1;
+        if self.seat >= self.states.len() {
+            self.seat = 0;
+            self.turn += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    fn session(rules: RuleSet, seats: usize) -> GameSession<SmallRng> {
+        GameSession::new(rules, seats, SmallRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn test_check_keep() {
+        let dice = vec![1, 2, 2, 5, 6];
+        assert_eq!(check_keep(&dice, &vec![2, 2, 6]), Ok(()));
+        assert_eq!(check_keep(&dice, &vec![2, 2, 2]),
+                   Err(RuleViolation::InvalidKeep { dice: dice.clone(), keep: vec![2, 2, 2] }));
+        assert!(check_keep(&dice, &vec![1, 2, 2, 3, 5, 6]).is_err());
+    }
+
+    #[test]
+    fn test_check_entry() {
+        let mut card = Scorecard::new(&RuleSet::yahtzee());
+        let dice = vec![3, 3, 3, 5, 5];
+        assert_eq!(check_entry(&card, &dice, (0, (FullHouse, 25))), Ok(()));
+        assert_eq!(check_entry(&card, &dice, (0, (FullHouse, 40))),
+                   Err(RuleViolation::WrongValue { entry: (0, (FullHouse, 40)), expected: 25 }));
+        assert_eq!(check_entry(&card, &dice, (1, (FullHouse, 25))),
+                   Err(RuleViolation::IllegalBox { column: 1, category: FullHouse }));
+        card.add(&dice, (0, (FullHouse, 25)));
+        assert_eq!(check_entry(&card, &dice, (0, (FullHouse, 25))),
+                   Err(RuleViolation::IllegalBox { column: 0, category: FullHouse }));
+    }
+
+    #[test]
+    fn test_check_announcement() {
+        let card = Scorecard::new(&RuleSet::kniffel());
+        assert_eq!(check_announcement(&card, None), Ok(()));
+        assert_eq!(check_announcement(&card, Some((3, Chance))), Ok(()));
+        assert!(check_announcement(&card, Some((2, Chance))).is_err());
+    }
+
+    #[test]
+    fn test_generala_no_bonus_event() {
+        let mut session = session(RuleSet::generala(), 1);
+        session.apply_keep(session.dice().clone()).unwrap();
+        let dice = session.dice().clone();
+        let entry = session.card().entry_of_category(&dice, 0, Sixes);
+        session.apply_entry(entry).unwrap();
+        assert!(!session.states()[0].card.column(0).has_bonus());
+        assert!(!session.take_events().iter().any(|event| matches!(event, GameEvent::BonusReached { .. })));
+    }
+
+    #[test]
+    fn test_turn_order() {
+        let mut session = session(RuleSet::yahtzee(), 2);
+        assert_eq!(session.pending_action(), Action::Keep { seat: 0, remaining_tosses: 2 });
+        let dice = session.dice().clone();
+        session.apply_keep(vec![]).unwrap();
+        assert_eq!(session.pending_action(), Action::Keep { seat: 0, remaining_tosses: 1 });
+        assert_eq!(session.toss(), 2);
+        session.apply_keep(session.dice().clone()).unwrap();
+        assert_eq!(session.pending_action(), Action::Entry { seat: 0 });
+        assert!(session.apply_keep(dice).is_err());
+        let entry = session.card().entry_of_category(session.dice(), 0, Chance);
+        session.apply_entry(entry).unwrap();
+        assert_eq!(session.pending_action(), Action::Keep { seat: 1, remaining_tosses: 2 });
+        assert_eq!(session.state(0).history.len(), 1);
+    }
+
+    #[test]
+    fn test_finished() {
+        let mut session = session(RuleSet::yahtzee(), 1);
+        while !session.is_finished() {
+            session.forfeit();
+        }
+        assert_eq!(session.pending_action(), Action::Finished);
+        assert_eq!(session.turn(), 13);
+        assert_eq!(session.state(0).card.open(), 0);
+    }
+
+    #[test]
+    fn test_disqualify() {
+        let mut session = session(RuleSet::yahtzee(), 2);
+        session.disqualify();
+        assert_eq!(session.seat(), 1);
+        session.forfeit();
+        assert_eq!(session.seat(), 1);
+        assert_eq!(session.turn(), 1);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut session = session(RuleSet::yahtzee(), 2);
+        assert!(!session.undo());
+        let first = session.dice().clone();
+        session.apply_keep(vec![]).unwrap();
+        let second = session.dice().clone();
+        session.apply_keep(second.clone()).unwrap();
+        let entry = session.card().entry_of_category(&second, 0, Chance);
+        session.apply_entry(entry).unwrap();
+        assert_eq!(session.seat(), 1);
+
+        assert!(session.undo());
+        assert_eq!(session.seat(), 0);
+        assert_eq!(session.pending_action(), Action::Entry { seat: 0 });
+        assert_eq!(session.dice(), &second);
+        assert!(session.state(0).history.is_empty());
+        assert_eq!(session.state(0).card.value(), 0);
+        assert!(session.undo());
+        assert!(session.undo());
+        assert_eq!(session.pending_action(), Action::Keep { seat: 0, remaining_tosses: 2 });
+        assert_eq!(session.dice(), &first);
+        assert!(!session.can_undo());
+
+        assert!(session.redo());
+        assert_eq!(session.dice(), &second);
+        assert!(session.redo());
+        assert!(session.redo());
+        assert!(!session.redo());
+        assert_eq!(session.seat(), 1);
+        let turn = &session.state(0).history[0];
+        assert_eq!((turn.dice(), turn.entry), (&second, entry));
+        assert_eq!(turn.tosses, vec![first, second.clone()]);
+        assert_eq!(turn.keeps, vec![vec![], second.clone()]);
+
+        session.undo();
+        session.apply_keep(vec![]).unwrap_err();
+        let other = session.card().entry_of_category(session.dice(), 0, Ones);
+        session.apply_entry(other).unwrap();
+        assert!(!session.can_redo());
+    }
+
+    #[test]
+    fn test_undo_reroll() {
+        let mut session = session(RuleSet::yahtzee(), 1);
+        session.set_undo_policy(UndoPolicy::Reroll);
+        session.apply_keep(vec![]).unwrap();
+        session.apply_keep(vec![]).unwrap();
+        let entry = session.card().entry_of_category(session.dice(), 0, Chance);
+        session.apply_entry(entry).unwrap();
+        session.apply_keep(vec![]).unwrap();
+        assert!(session.undo());
+        assert_eq!(session.turn(), 1);
+        assert_eq!(session.toss(), 1);
+        assert!(session.undo());
+        assert_eq!(session.turn(), 0);
+        assert_eq!(session.pending_action(), Action::Keep { seat: 0, remaining_tosses: 2 });
+        assert!(session.state(0).history.is_empty());
+        assert!(!session.can_undo());
+        assert!(!session.can_redo());
+    }
+
+    #[test]
+    fn test_undo_saved_rolls() {
+        let mut session = session(RuleSet::maxi_yatzy(), 1);
+        session.apply_keep(session.dice().clone()).unwrap();
+        assert_eq!(session.state(0).roll_bank, 2);
+        assert!(session.undo());
+        assert_eq!(session.state(0).roll_bank, 0);
+        assert_eq!(session.pending_action(), Action::Keep { seat: 0, remaining_tosses: 2 });
+    }
+
+    #[test]
+    fn test_announce_phase() {
+        let mut session = session(RuleSet::kniffel(), 1);
+        assert_eq!(session.pending_action(), Action::Announce { seat: 0 });
+        assert!(session.apply_announce(Some((0, Ones))).is_err());
+        session.apply_announce(Some((3, Chance))).unwrap();
+        assert_eq!(session.card().announcement(), Some((3, Chance)));
+        assert_eq!(session.pending_action(), Action::Keep { seat: 0, remaining_tosses: 2 });
+    }
+}
@@ -0,0 +1,567 @@
+use ai::YahtzeeAI;
+use dice::Dice;
+use player::Player;
+use probability::TossTable;
+use result::Result;
+use rules::{Order, RuleSet, Variant};
+use table::TableView;
+use types::{Category, ColumnEntry, DiceCategory, Value};
+use types::Category::*;
+use strategy::{StrategyTable, TableError};
+use std::sync::OnceLock;
+
+/// Where a solitaire game stands between two turns, as far as the rest of
+/// the game is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct State {
+    /// a bit for every filled box in the order of the rules' categories
+    pub filled: u32,
+    /// the upper subtotal, capped at what earns the bonus
+    pub upper: u16,
+    /// the Yahtzee box was scored with full points, so more Yahtzees earn a bonus
+    pub yahtzee: bool,
+}
+
+/// Expected final scores of every state of a game with a single free
+/// column, computed by dynamic programming from the last turn back.
+pub struct Solver {
+    rules: RuleSet,
+    /// the upper subtotal that earns the bonus, 0 if the bonus does not matter
+    cap: u16,
+    /// 2 if whether Yahtzee was scored changes later points, 1 otherwise
+    flags: usize,
+    tosses: TossTable,
+    /// value and Joker value of every outcome in every category
+    scores: Vec<Vec<(Value, Value)>>,
+    yahtzees: Vec<bool>,
+    /// the upper box that has to take a Joker of the outcome
+    joker_boxes: Vec<Option<usize>>,
+    yahtzee_box: Option<usize>,
+    lower_boxes: u32,
+    /// expected points still to come in every state, see `index`
+    values: Vec<f32>,
+    /// expected square of the points still to come, see `solve_variances`
+    squares: Vec<f32>,
+}
+
+impl Solver {
+    /// rules with one free column, at most 16 boxes and no saved rolls or
+    /// bonuses for the first toss
+    pub fn supports(rules: &RuleSet) -> bool {
+        rules.columns.len() == 1 && rules.columns[0].order == Order::Free
+            && rules.variant != Variant::Generala && !rules.save_rolls
+            && rules.categories.len() <= 16 && rules.toss_num > 0
+    }
+
+    /// a solver that still has to `solve`, `None` if the rules are not supported
+    pub fn new(rules: &RuleSet) -> Option<Solver> {
+        if !Solver::supports(rules) {
+            return None
+        }
+        let has_upper = rules.categories.iter().any(Category::is_upper);
+        let cap = if has_upper && rules.bonus_value > 0 { rules.bonus_reach as u16 } else { 0 };
+        let flags = if rules.yahtzee_bonus_value > 0 || rules.categories.contains(&DoubleYahtzee) { 2 } else { 1 };
+        let tosses = TossTable::new(&rules.dice_faces, rules.dice_num as usize);
+        let outcomes = tosses.outcomes();
+        let scores = outcomes.iter().map(|dice| {
+            rules.categories.iter()
+                .map(|cat| (dice.entry_of_category(rules, *cat).1, dice.joker_value_of_category(rules, cat)))
+                .collect()
+        }).collect();
+        let yahtzees = outcomes.iter().map(|dice| dice.is_category(rules, &Yahtzee)).collect();
+        let joker_boxes = outcomes.iter().map(|dice| {
+            let upper = Category::upper_of(dice[0]);
+            rules.categories.iter().position(|&cat| cat == upper)
+        }).collect();
+        let lower_boxes = rules.categories.iter().enumerate()
+            .filter(|&(_, cat)| cat.is_lower())
+            .fold(0, |boxes, (i, _)| boxes | 1 << i);
+        Some(Solver {
+            rules: rules.clone(),
+            cap, flags, scores, yahtzees, joker_boxes,
+            yahtzee_box: rules.categories.iter().position(|&cat| cat == Yahtzee),
+            lower_boxes,
+            tosses,
+            values: Vec::new(),
+            squares: Vec::new(),
+        })
+    }
+
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// a solved solver from the values of an earlier `solve`, `None` if
+    /// there are not as many as the rules need
+    pub fn with_values(rules: &RuleSet, values: Vec<f32>) -> Option<Solver> {
+        let mut solver = Solver::new(rules)?;
+        if values.len() != solver.value_count() {
+            return None
+        }
+        solver.values = values;
+        Some(solver)
+    }
+
+    /// the number of states, one value each once solved
+    pub fn value_count(&self) -> usize {
+        (1usize << self.rules.categories.len()) * (self.cap as usize + 1) * self.flags
+    }
+
+    /// the expected points of every state, empty before `solve`
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn is_solved(&self) -> bool {
+        !self.values.is_empty()
+    }
+
+    /// computes the value of every state that can be reached
+    pub fn solve(&mut self) {
+        let boxes = self.rules.categories.len();
+        let full = (1u32 << boxes) - 1;
+        self.values = vec![0f32; self.value_count()];
+        for filled in (0..full).rev() {
+            let reachable = self.reachable_uppers(filled);
+            for upper in 0..=self.cap {
+                if !reachable[upper as usize] {
+                    continue
+                }
+                for flag in 0..self.flags {
+                    let yahtzee = flag == 1;
+                    if yahtzee && !self.yahtzee_filled(filled) {
+                        continue
+                    }
+                    let state = State { filled, upper, yahtzee };
+                    let value = self.turn_value(state);
+                    let index = self.index(state);
+                    self.values[index] = value as f32;
+                }
+            }
+        }
+    }
+
+    /// Computes how much the points still to come vary in every state when
+    /// the highest expected score is played, after `solve`.
+    pub fn solve_variances(&mut self) {
+        if !self.is_solved() {
+            return
+        }
+        let boxes = self.rules.categories.len();
+        let full = (1u32 << boxes) - 1;
+        self.squares = vec![0f32; self.values.len()];
+        for filled in (0..full).rev() {
+            let reachable = self.reachable_uppers(filled);
+            for upper in 0..=self.cap {
+                if !reachable[upper as usize] {
+                    continue
+                }
+                for flag in 0..self.flags {
+                    let yahtzee = flag == 1;
+                    if yahtzee && !self.yahtzee_filled(filled) {
+                        continue
+                    }
+                    let state = State { filled, upper, yahtzee };
+                    let square = self.turn_square(state);
+                    let index = self.index(state);
+                    self.squares[index] = square as f32;
+                }
+            }
+        }
+    }
+
+    pub fn has_variances(&self) -> bool {
+        !self.squares.is_empty()
+    }
+
+    /// the expected square of the points still to come in every state,
+    /// empty before `solve_variances`
+    pub fn squares(&self) -> &[f32] {
+        &self.squares
+    }
+
+    /// the squares of an earlier `solve_variances`, `false` if there are not
+    /// as many as values
+    pub(crate) fn set_squares(&mut self, squares: Vec<f32>) -> bool {
+        if squares.len() != self.values.len() {
+            return false
+        }
+        self.squares = squares;
+        true
+    }
+
+    /// variance of the points still to come, 0 before `solve_variances`
+    pub fn variance(&self, state: State) -> f64 {
+        match self.squares.get(self.index(state)) {
+            Some(&square) => (square as f64 - self.value(state).powi(2)).max(0f64),
+            None => 0f64,
+        }
+    }
+
+    /// expected final score of a new game
+    pub fn expected_score(&self) -> f64 {
+        self.value(State { filled: 0, upper: 0, yahtzee: false })
+    }
+
+    /// expected points still to come
+    pub fn value(&self, state: State) -> f64 {
+        self.values[self.index(state)] as f64
+    }
+
+    pub fn state(&self, result: &Result) -> State {
+        let filled = self.rules.categories.iter().enumerate()
+            .filter(|&(_, &cat)| result.has(cat))
+            .fold(0, |filled, (i, _)| filled | 1 << i);
+        State {
+            filled,
+            upper: (result.value_upper() as u16).min(self.cap),
+            yahtzee: self.flags == 2 && result.get(Yahtzee) == Some(self.rules.yahtzee_value),
+        }
+    }
+
+    /// the keep with the highest expected score
+    pub fn best_keep(&self, state: State, remaining_tosses: u8, dice: &Dice) -> Dice {
+        self.tosses.best_keep(self.dice_values(state, 0), remaining_tosses, dice).unwrap_or_else(|| {
+            let mut sorted = dice.clone();
+            sorted.sort_unstable();
+            sorted
+        })
+    }
+
+    /// the box with the highest expected score for the final dice
+    pub fn best_entry(&self, state: State, dice: &Dice) -> Option<Category> {
+        let outcome = self.tosses.outcome(dice)?;
+        self.entry(state, outcome).map(|(cat, _)| self.rules.categories[cat])
+    }
+
+    pub(crate) fn tosses(&self) -> &TossTable {
+        &self.tosses
+    }
+
+    fn index(&self, state: State) -> usize {
+        (state.filled as usize * (self.cap as usize + 1) + state.upper as usize) * self.flags + state.yahtzee as usize
+    }
+
+    fn yahtzee_filled(&self, filled: u32) -> bool {
+        self.yahtzee_box.is_some_and(|i| filled & 1 << i != 0)
+    }
+
+    /// upper subtotals the filled upper boxes can add up to
+    fn reachable_uppers(&self, filled: u32) -> Vec<bool> {
+        let mut reachable = vec![false; self.cap as usize + 1];
+        reachable[0] = true;
+        for (i, &cat) in self.rules.categories.iter().enumerate() {
+            if filled & 1 << i == 0 || !cat.is_upper() {
+                continue
+            }
+            let face = Category::upper_into_iter().position(|&upper| upper == cat).expect("an upper box") + 1;
+            let mut next = vec![false; reachable.len()];
+            for (upper, _) in reachable.iter().enumerate().filter(|&(_, &r)| r) {
+                for count in 0..=self.rules.dice_num as usize {
+                    next[(upper + count * face).min(self.cap as usize)] = true;
+                }
+            }
+            reachable = next;
+        }
+        reachable
+    }
+
+    /// the points and the following state when the outcome is written to
+    /// the box, `None` if it may not
+    pub(crate) fn write(&self, state: State, outcome: usize, cat: usize) -> Option<(f64, State)> {
+        if state.filled & 1 << cat != 0 {
+            return None
+        }
+        let category = self.rules.categories[cat];
+        let yahtzee = self.yahtzees[outcome];
+        let joker = self.rules.joker && yahtzee && self.yahtzee_filled(state.filled);
+        if joker {
+            match self.joker_boxes[outcome] {
+                Some(upper) if state.filled & 1 << upper == 0 => if cat != upper {
+                    return None
+                },
+                _ => if category.is_upper() && state.filled & self.lower_boxes != self.lower_boxes {
+                    return None
+                },
+            }
+        }
+        let (value, joker_value) = self.scores[outcome][cat];
+        let mut value = if joker { joker_value } else { value } as u32;
+        if category == DoubleYahtzee && !state.yahtzee {
+            value = 0;
+        }
+        let mut points = value;
+        if state.yahtzee && yahtzee {
+            points += self.rules.yahtzee_bonus_value;
+        }
+        let mut upper = state.upper;
+        if category.is_upper() && self.cap > 0 {
+            upper = (state.upper as u32 + value).min(self.cap as u32) as u16;
+            if state.upper < self.cap && upper == self.cap {
+                points += self.rules.bonus_value;
+            }
+        }
+        let scored = self.flags == 2 && category == Yahtzee && value == self.rules.yahtzee_value as u32;
+        Some((points as f64, State { filled: state.filled | 1 << cat, upper, yahtzee: state.yahtzee || scored }))
+    }
+
+    /// the best box for the outcome with its expected score
+    fn entry(&self, state: State, outcome: usize) -> Option<(usize, f64)> {
+        (0..self.rules.categories.len())
+            .filter_map(|cat| self.write(state, outcome, cat).map(|(points, next)| (cat, points + self.value(next))))
+            .fold(None, |best: Option<(usize, f64)>, (cat, value)| match best {
+                Some((_, best_value)) if best_value >= value => best,
+                _ => Some((cat, value)),
+            })
+    }
+
+    /// expected score of every outcome with the given tosses left
+    fn dice_values(&self, state: State, remaining_tosses: u8) -> Vec<f64> {
+        let values = (0..self.tosses.outcomes().len())
+            .map(|outcome| self.entry(state, outcome).map_or(0f64, |(_, value)| value))
+            .collect();
+        self.tosses.best_values(values, remaining_tosses)
+    }
+
+    fn turn_value(&self, state: State) -> f64 {
+        let values = self.dice_values(state, self.rules.toss_num - 1);
+        let first = self.tosses.keep(&Dice::new()).expect("the empty keep");
+        self.tosses.rolls(first).iter().map(|&(outcome, probability)| probability * values[outcome]).sum()
+    }
+
+    /// like `turn_value` for the square of the points, the keeps and boxes
+    /// are the ones with the highest expected score
+    fn turn_square(&self, state: State) -> f64 {
+        let tosses = &self.tosses;
+        let (mut values, mut squares): (Vec<f64>, Vec<f64>) = (0..tosses.outcomes().len()).map(|outcome| {
+            let best = self.entry(state, outcome)
+                .and_then(|(cat, value)| self.write(state, outcome, cat).map(|written| (value, written)));
+            match best {
+                Some((value, (points, next))) => {
+                    let square = points * points + 2.0 * points * self.value(next) + self.squares[self.index(next)] as f64;
+                    (value, square)
+                }
+                None => (0f64, 0f64),
+            }
+        }).unzip();
+        for _ in 1..self.rules.toss_num {
+            let keep_values = tosses.keep_values(&values);
+            let keep_squares = tosses.keep_values(&squares);
+            for outcome in 0..values.len() {
+                let best = tosses.outcome_keeps(outcome).iter().cloned()
+                    .max_by(|&a, &b| keep_values[a].partial_cmp(&keep_values[b]).expect("values are numbers"))
+                    .expect("every outcome can be kept");
+                values[outcome] = keep_values[best];
+                squares[outcome] = keep_squares[best];
+            }
+        }
+        let first = tosses.keep(&Dice::new()).expect("the empty keep");
+        tosses.rolls(first).iter().map(|&(outcome, probability)| probability * squares[outcome]).sum()
+    }
+}
+
+/// the values of the table if it can be used for the rules, solved
+/// otherwise, and why the table could not be used
+pub(crate) fn load_or_solve(table: Option<&StrategyTable>, rules: &RuleSet) -> (Option<Solver>, Option<TableError>) {
+    let loaded = table.map(|table| table.load(rules));
+    match loaded {
+        Some(Ok(solver)) => (Some(solver), None),
+        loaded => {
+            let solved = Solver::new(rules).map(|mut solver| {
+                solver.solve();
+                solver
+            });
+            (solved, loaded.and_then(|loaded| loaded.err()))
+        }
+    }
+}
+
+/// the warning of a player that solved as the table could not be used
+pub(crate) fn table_warning(table: Option<&StrategyTable>, error: Option<TableError>) -> Option<String> {
+    Some(format!("can not use table {}: {}, solved instead", table?.path().display(), error?))
+}
+
+/// Plays a single free column for the highest expected score. The solver is
+/// loaded from the table or solved when the game starts, for rules it does
+/// not support the player decides like a hard `YahtzeeAI`.
+pub struct OptimalAI {
+    name: String,
+    table: Option<StrategyTable>,
+    solver: OnceLock<Option<Solver>>,
+    fallback: YahtzeeAI,
+}
+
+impl OptimalAI {
+    pub fn new(name: &str) -> OptimalAI {
+        OptimalAI { name: String::from(name), table: None, solver: OnceLock::new(), fallback: YahtzeeAI::new() }
+    }
+
+    /// a player that reads its values from the table, a table made for other
+    /// rules than the game's is not used
+    pub fn with_table(name: &str, table: StrategyTable) -> OptimalAI {
+        OptimalAI { table: Some(table), ..OptimalAI::new(name) }
+    }
+
+    /// a player with a solved solver for the rules
+    pub fn with_solver(name: &str, solver: Solver) -> OptimalAI {
+        let player = OptimalAI::new(name);
+        player.solver.set(Some(solver)).ok();
+        player
+    }
+
+    /// loads or solves once, the warning if the table could not be used
+    fn init(&self, rules: &RuleSet) -> Option<String> {
+        let mut warning = None;
+        self.solver.get_or_init(|| {
+            let (solver, error) = load_or_solve(self.table.as_ref(), rules);
+            warning = table_warning(self.table.as_ref(), error);
+            solver
+        });
+        warning
+    }
+
+    fn solver(&self, rules: &RuleSet) -> Option<&Solver> {
+        self.init(rules);
+        self.solver.get()?.as_ref().filter(|solver| solver.rules() == rules && solver.is_solved())
+    }
+}
+
+impl Player for OptimalAI {
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice {
+        match self.solver(table.rules()) {
+            Some(solver) => solver.best_keep(solver.state(table.card().column(0)), remaining_tosses, dice),
+            None => self.fallback.decide_keep(table, remaining_tosses, dice),
+        }
+    }
+
+    fn decide_entry(&self, table: &TableView, dice: &Dice) -> ColumnEntry {
+        let card = table.card();
+        let best = self.solver(table.rules())
+            .and_then(|solver| solver.best_entry(solver.state(card.column(0)), dice));
+        match best {
+            Some(category) => card.entry_of_category(dice, 0, category),
+            None => self.fallback.decide_entry(table, dice),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// loads or solves the solver, which takes long without a table
+    fn prepare(&self, rules: &RuleSet) -> Option<String> {
+        self.init(rules)
+    }
+
+    fn spec(&self) -> Option<String> {
+        match self.table {
+            Some(ref table) => Some(format!("optimal:{}:{}", self.name, table.path().display())),
+            None => Some(format!("optimal:{}", self.name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::GameState;
+    use strategy;
+    use std::env;
+
+    fn rules(categories: Vec<Category>) -> RuleSet {
+        RuleSet { categories, ..RuleSet::yahtzee() }
+    }
+
+    fn solved(rules: &RuleSet) -> Solver {
+        let mut solver = Solver::new(rules).unwrap();
+        solver.solve();
+        solver
+    }
+
+    /// a table of the solved rules
+    fn table(rules: &RuleSet) -> StrategyTable {
+        let path = env::temp_dir().join("yahtzee-solver-test.bin");
+        let mut solver = solved(rules);
+        solver.solve_variances();
+        strategy::write(&solver, &path).unwrap();
+        StrategyTable::open(&path).unwrap()
+    }
+
+    #[test]
+    fn test_single_box() {
+        // known optimal expectations of a game with only that box
+        let chance = solved(&rules(vec![Chance])).expected_score();
+        assert!((chance - 23.3338).abs() < 1e-3, "{}", chance);
+        let yahtzee = solved(&rules(vec![Yahtzee])).expected_score();
+        assert!((yahtzee - 50.0 * 0.046029).abs() < 1e-3, "{}", yahtzee);
+    }
+
+    #[test]
+    fn test_two_boxes() {
+        // the best first turn of a two box game, worked out without states:
+        // the box taken scores and the other is played alone in the last turn
+        let rules = rules(vec![Chance, FullHouse]);
+        let tosses = TossTable::new(&rules.dice_faces, rules.dice_num as usize);
+        let turn = |values: Vec<f64>| tosses.keep_values(&tosses.best_values(values, 2))[0];
+        let score = |dice: &Dice, cat: Category| dice.entry_of_category(&rules, cat).1 as f64;
+        let alone = |cat| turn(tosses.outcomes().iter().map(|dice| score(dice, cat)).collect());
+        let (chance, full_house) = (alone(Chance), alone(FullHouse));
+        assert!((chance - 23.3338).abs() < 1e-3, "{}", chance);
+        let both = turn(tosses.outcomes().iter()
+            .map(|dice| (score(dice, Chance) + full_house).max(score(dice, FullHouse) + chance))
+            .collect());
+        let expected = solved(&rules).expected_score();
+        assert!((expected - both).abs() < 1e-3, "{} {}", expected, both);
+    }
+
+    #[test]
+    fn test_variances() {
+        let mut solver = solved(&rules(vec![Yahtzee]));
+        let start = State { filled: 0, upper: 0, yahtzee: false };
+        assert_eq!(solver.variance(start), 0.0);
+        solver.solve_variances();
+        // a Yahtzee or nothing
+        let chance = 0.046029;
+        assert!((solver.variance(start) - 2500.0 * chance * (1.0 - chance)).abs() < 0.1, "{}", solver.variance(start));
+        let mut solver = solved(&rules(vec![Chance]));
+        solver.solve_variances();
+        assert!(solver.variance(start) > 0.0 && solver.variance(start) < 25.0);
+    }
+
+    #[test]
+    fn test_decisions() {
+        let rules = rules(vec![Fives, Sixes, Yahtzee, Chance]);
+        let solver = solved(&rules);
+        let start = State { filled: 0, upper: 0, yahtzee: false };
+        assert_eq!(solver.best_keep(start, 2, &vec![6, 6, 6, 6, 1]), vec![6, 6, 6, 6]);
+        assert_eq!(solver.best_entry(start, &vec![6, 6, 6, 6, 6]), Some(Yahtzee));
+        let states = vec![GameState::new(&rules)];
+        let table = TableView::new(&states, &[], 0, 0);
+        let player = OptimalAI::with_solver("Optimal", solver);
+        // a Yahtzee is unlikely in three turns, Chance is worth more later
+        assert_eq!(player.decide_entry(&table, &vec![1, 2, 3, 4, 5]), (0, (Yahtzee, 0)));
+        assert!(!Solver::supports(&RuleSet::kniffel()));
+    }
+
+    #[test]
+    fn test_prepare() {
+        let rules = rules(vec![Yahtzee, Chance]);
+        let player = OptimalAI::with_table("Optimal", table(&rules));
+        let warning = player.prepare(&RuleSet { categories: vec![Chance], ..rules.clone() });
+        assert!(warning.unwrap().contains("other rules"));
+        assert!(player.solver.get().is_some_and(|solver| solver.as_ref().is_some_and(Solver::is_solved)));
+        assert_eq!(OptimalAI::with_table("Optimal", table(&rules)).prepare(&rules), None);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_yahtzee_optimum() {
+        // a minute and a half with `cargo test --release -- --ignored`
+        let mut solver = solved(&RuleSet::yahtzee());
+        let expected = solver.expected_score();
+        assert!((expected - 254.5896).abs() < 0.01, "{}", expected);
+        solver.solve_variances();
+        let spread = solver.variance(State { filled: 0, upper: 0, yahtzee: false }).sqrt();
+        assert!((spread - 59.6).abs() < 0.1, "{}", spread);
+    }
+}
@@ -0,0 +1,232 @@
+//! Solved values of a `Solver` in a binary file, so that a player does not
+//! have to solve the game before its first decision. All numbers are little
+//! endian:
+//!
+//! ```text
+//! magic     8 bytes  "yzsolver"
+//! version   u32
+//! rules     u64      FNV-1a hash of the rules the values are for
+//! count     u64      number of values
+//! checksum  u64      FNV-1a hash of the values and squares
+//! values    f32 * count
+//! squares   f32 * count  expected squares of the points, for the variances
+//! ```
+
+use rules::RuleSet;
+use save;
+use solver::Solver;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 8] = b"yzsolver";
+const VERSION: u32 = 2;
+const HEADER_LEN: u64 = 36;
+
+/// Why a table could not be written or used.
+#[derive(Debug)]
+pub enum TableError {
+    Io(io::Error),
+    /// not a table file
+    Format,
+    Version(u32),
+    /// made for other rules
+    Rules,
+    /// the values do not match the checksum or the size of the rules
+    Corrupt,
+    /// the solver has no values or variances yet or can not solve the rules
+    Unsolved,
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableError::Io(err) => write!(f, "{}", err),
+            TableError::Format => write!(f, "not a strategy table"),
+            TableError::Version(version) => write!(f, "unsupported table version {}", version),
+            TableError::Rules => write!(f, "table made for other rules"),
+            TableError::Corrupt => write!(f, "table is damaged"),
+            TableError::Unsolved => write!(f, "nothing solved to write"),
+        }
+    }
+}
+
+impl Error for TableError {}
+
+impl From<io::Error> for TableError {
+    fn from(err: io::Error) -> TableError {
+        TableError::Io(err)
+    }
+}
+
+pub type Loaded<T> = ::std::result::Result<T, TableError>;
+
+/// FNV-1a, 64 bit
+fn fnv(bytes: &[u8], mut hash: u64) -> u64 {
+    for &byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// the same for equal rules, made from the fields of a save file
+pub fn rules_hash(rules: &RuleSet) -> u64 {
+    save::rule_fields(rules).iter()
+        .fold(FNV_OFFSET, |hash, (key, value)| fnv(format!("{}={}\n", key, value).as_bytes(), hash))
+}
+
+/// writes the values and squares of a solver after `solve_variances`
+pub fn write(solver: &Solver, path: &Path) -> Loaded<()> {
+    if !solver.is_solved() || !solver.has_variances() {
+        return Err(TableError::Unsolved)
+    }
+    let values = solver.values();
+    let checksum = values.iter().chain(solver.squares())
+        .fold(FNV_OFFSET, |hash, value| fnv(&value.to_le_bytes(), hash));
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+    out.write_all(&rules_hash(solver.rules()).to_le_bytes())?;
+    out.write_all(&(values.len() as u64).to_le_bytes())?;
+    out.write_all(&checksum.to_le_bytes())?;
+    for value in values.iter().chain(solver.squares()) {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// The header of a table file. The values are only read by `load`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StrategyTable {
+    path: PathBuf,
+    rules: u64,
+    count: u64,
+    checksum: u64,
+}
+
+impl StrategyTable {
+    /// reads and checks the header
+    pub fn open(path: &Path) -> Loaded<StrategyTable> {
+        let mut header = [0u8; HEADER_LEN as usize];
+        File::open(path)?.read_exact(&mut header).map_err(|err| match err.kind() {
+            io::ErrorKind::UnexpectedEof => TableError::Format,
+            _ => TableError::Io(err),
+        })?;
+        if &header[..8] != MAGIC {
+            return Err(TableError::Format)
+        }
+        let version = u32::from_le_bytes([header[8], header[9], header[10], header[11]]);
+        if version != VERSION {
+            return Err(TableError::Version(version))
+        }
+        let number = |at: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&header[at..at + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        Ok(StrategyTable { path: path.to_path_buf(), rules: number(12), count: number(20), checksum: number(28) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// whether the table was made for the rules
+    pub fn fits(&self, rules: &RuleSet) -> bool {
+        self.rules == rules_hash(rules)
+    }
+
+    /// a solved solver with variances for the rules from the file
+    pub fn load(&self, rules: &RuleSet) -> Loaded<Solver> {
+        if !self.fits(rules) {
+            return Err(TableError::Rules)
+        }
+        let count = Solver::new(rules).ok_or(TableError::Unsolved)?.value_count();
+        if self.count != count as u64 {
+            return Err(TableError::Corrupt)
+        }
+        let mut file = File::open(&self.path)?;
+        let len = HEADER_LEN + 8 * count as u64;
+        if file.metadata()?.len() != len {
+            return Err(TableError::Corrupt)
+        }
+        let mut bytes = Vec::with_capacity(len as usize);
+        file.read_to_end(&mut bytes)?;
+        let bytes = bytes.get(HEADER_LEN as usize..).filter(|values| values.len() == 8 * count)
+            .ok_or(TableError::Corrupt)?;
+        let checksum = fnv(bytes, FNV_OFFSET);
+        if checksum != self.checksum {
+            return Err(TableError::Corrupt)
+        }
+        let mut values: Vec<f32> = bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        let squares = values.split_off(count);
+        let mut solver = Solver::with_values(rules, values).ok_or(TableError::Corrupt)?;
+        if !solver.set_squares(squares) {
+            return Err(TableError::Corrupt)
+        }
+        Ok(solver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+    use std::env;
+    use std::fs;
+
+    fn rules() -> RuleSet {
+        RuleSet { categories: vec![Ones, Sixes, Yahtzee, Chance], ..RuleSet::yahtzee() }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = env::temp_dir().join("yahtzee-strategy-test.bin");
+        let mut solver = Solver::new(&rules()).unwrap();
+        assert!(matches!(write(&solver, &path), Err(TableError::Unsolved)));
+        solver.solve();
+        assert!(matches!(write(&solver, &path), Err(TableError::Unsolved)));
+        solver.solve_variances();
+        write(&solver, &path).unwrap();
+
+        let table = StrategyTable::open(&path).unwrap();
+        assert!(table.fits(&rules()));
+        let loaded = table.load(&rules()).unwrap();
+        assert_eq!(loaded.values(), solver.values());
+        assert_eq!(loaded.squares(), solver.squares());
+        assert_eq!(loaded.expected_score(), solver.expected_score());
+        assert!(matches!(table.load(&RuleSet::yahtzee()), Err(TableError::Rules)));
+
+        fs::write(&path, &fs::read(&path).unwrap()[..HEADER_LEN as usize + 4]).unwrap();
+        assert!(matches!(StrategyTable::open(&path).unwrap().load(&rules()), Err(TableError::Corrupt)));
+        write(&solver, &path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(StrategyTable::open(&path).unwrap().load(&rules()), Err(TableError::Corrupt)));
+        // a count the rules do not have is refused before reading the values
+        bytes[20..28].copy_from_slice(&u64::MAX.to_le_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(StrategyTable::open(&path).unwrap().load(&rules()), Err(TableError::Corrupt)));
+        bytes[8] = 9;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(StrategyTable::open(&path), Err(TableError::Version(9))));
+        fs::write(&path, b"yahtzee-save 3\n").unwrap();
+        assert!(matches!(StrategyTable::open(&path), Err(TableError::Format)));
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_rules_hash() {
+        assert_eq!(rules_hash(&RuleSet::yahtzee()), rules_hash(&RuleSet::yahtzee()));
+        assert_ne!(rules_hash(&RuleSet::yahtzee()), rules_hash(&RuleSet::triple_yahtzee()));
+        assert_ne!(rules_hash(&RuleSet::yahtzee()), rules_hash(&rules()));
+    }
+}
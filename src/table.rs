@@ -0,0 +1,98 @@
+use game::GameState;
+use rules::RuleSet;
+use scorecard::Scorecard;
+
+/// What a player may look at when deciding: every seat at the table, whose
+/// turn it is and the rules.
+#[derive(Clone, Copy)]
+pub struct TableView<'a> {
+    states: &'a [GameState],
+    names: &'a [String],
+    seat: usize,
+    turn: usize,
+}
+
+impl<'a> TableView<'a> {
+    pub fn new(states: &'a [GameState], names: &'a [String], seat: usize, turn: usize) -> TableView<'a> {
+        TableView { states, names, seat, turn }
+    }
+
+    pub fn rules(&self) -> &'a RuleSet {
+        self.card().rules()
+    }
+
+    pub fn states(&self) -> &'a [GameState] {
+        self.states
+    }
+
+    pub fn state(&self, seat: usize) -> &'a GameState {
+        &self.states[seat]
+    }
+
+    pub fn names(&self) -> &'a [String] {
+        self.names
+    }
+
+    /// name of the player at the seat, if known
+    pub fn name(&self, seat: usize) -> Option<&'a str> {
+        self.names.get(seat).map(|name| name.as_str())
+    }
+
+    /// the seat whose turn it is
+    pub fn seat(&self) -> usize {
+        self.seat
+    }
+
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn turns_left(&self) -> usize {
+        self.rules().turns().saturating_sub(self.turn)
+    }
+
+    pub fn toss(&self) -> u8 {
+        self.card().toss()
+    }
+
+    /// scorecard of the seat whose turn it is
+    pub fn card(&self) -> &'a Scorecard {
+        &self.states[self.seat].card
+    }
+
+    /// seats still in the game other than the deciding one
+    pub fn opponents(&self) -> Vec<usize> {
+        (0..self.states.len())
+            .filter(|&seat| seat != self.seat && !self.states[seat].disqualified)
+            .collect()
+    }
+
+    /// highest score of the opponents so far
+    pub fn best_opponent_value(&self) -> Option<u32> {
+        self.opponents().into_iter().map(|seat| self.states[seat].card.value()).max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::Category::*;
+
+    #[test]
+    fn test_view() {
+        let rules = RuleSet::yahtzee();
+        let mut states: Vec<GameState> = (0..3).map(|_| GameState::new(&rules)).collect();
+        states[0].write_entry(vec![6, 6, 6, 6, 1], (0, (Sixes, 24)));
+        states[2].write_entry(vec![1, 2, 3, 4, 5], (0, (LargeStraght, 40)));
+        states[2].disqualified = true;
+        let names = vec![String::from("A"), String::from("B")];
+        let view = TableView::new(&states, &names, 1, 2);
+        assert_eq!(view.card().value(), 0);
+        assert_eq!(view.opponents(), vec![0]);
+        assert_eq!(view.best_opponent_value(), Some(24));
+        assert_eq!(view.name(1), Some("B"));
+        assert_eq!(view.name(2), None);
+        assert_eq!(view.turns_left(), 11);
+        assert_eq!(view.state(0).history.len(), 1);
+    }
+}
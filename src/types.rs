@@ -1,5 +1,6 @@
 use self::Category::*;
-use dice::{Dice, MyDice, DICE_NUM};
+use dice::{Dice, Die, MyDice};
+use rules::{RuleSet, Variant};
 use std::slice::Iter;
 use std::iter::Chain;
 use std::collections::HashMap;
@@ -12,42 +13,62 @@ pub enum Category {
     Fours,
     Fives,
     Sixes,
+    OnePair,
+    TwoPairs,
+    ThreePairs,
     ThreeOfAKind,
     FourOfAKind,
+    FiveOfAKind,
     FullHouse,
+    Castle,
+    Tower,
     SmallStraight,
     LargeStraght,
+    FullStraight,
     Yahtzee,
+    DoubleYahtzee,
     Chance,
 }
 
 pub type Value = u8;
 pub type Entry = (Category, Value);
-
-pub const FULL_HOUSE_VALUE: Value = 25;
-pub const SMALL_STRAIGHT_VALUE: Value = 30;
-pub const LARGE_STRAIGHT_VALUE: Value = 40;
-pub const YAHTZEE_VALUE: Value = 50;
+/// entry in the scorecard column with the given index
+pub type ColumnEntry = (usize, Entry);
 
 impl Category {
     const UPPER_CATEGORIES: [Category; 6] = [Ones, Twos, Threes, Fours, Fives, Sixes];
     const LOWER_CATEGORIES: [Category; 7] = [ThreeOfAKind, FourOfAKind, FullHouse,
                                               SmallStraight, LargeStraght, Yahtzee, Chance];
 
+    const ALL_CATEGORIES: [Category; 21] = [Ones, Twos, Threes, Fours, Fives, Sixes,
+                                            OnePair, TwoPairs, ThreePairs, ThreeOfAKind,
+                                            FourOfAKind, FiveOfAKind, FullHouse, Castle, Tower,
+                                            SmallStraight, LargeStraght, FullStraight,
+                                            Yahtzee, DoubleYahtzee, Chance];
+
+    /// the category as it is written by `Debug`
+    pub fn by_name(name: &str) -> Option<Category> {
+        Category::ALL_CATEGORIES.iter().cloned().find(|cat| format!("{:?}", cat) == name)
+    }
+
     pub fn is_lower(&self) -> bool {
-        Category::LOWER_CATEGORIES.contains(self)
+        !self.is_upper()
     }
 
     pub fn is_upper(&self) -> bool {
         Category::UPPER_CATEGORIES.contains(self)
     }
 
+    pub fn upper_of(die: Die) -> Category {
+        Category::UPPER_CATEGORIES[die as usize - 1]
+    }
+
     pub fn upper_into_iter() -> Iter<'static, Category> {
-        Category::UPPER_CATEGORIES.into_iter()
+        Category::UPPER_CATEGORIES.iter()
     }
 
     pub fn lower_into_iter() -> Iter<'static, Category> {
-        Category::LOWER_CATEGORIES.into_iter()
+        Category::LOWER_CATEGORIES.iter()
     }
 
     pub fn into_iter() -> Chain<Iter<'static, Category>, Iter<'static, Category>>{
@@ -57,7 +78,7 @@ impl Category {
 
 pub trait MyEntry {
     fn is_upper(&self) -> bool;
-    fn upper_above_bonus(&self) -> i32;
+    fn upper_above_bonus(&self, rules: &RuleSet) -> i32;
 }
 
 impl MyEntry for Entry {
@@ -65,29 +86,32 @@ impl MyEntry for Entry {
         self.0.is_upper()
     }
 
-    fn upper_above_bonus(&self) -> i32 {
-        match self.0 {
-            Ones => self.1 as i32 - 3 * 1,
-            Twos => self.1 as i32 - 3 * 2,
-            Threes => self.1 as i32 - 3 * 3,
-            Fours => self.1 as i32 - 3 * 4,
-            Fives => self.1 as i32 - 3 * 5,
-            Sixes => self.1 as i32 - 3 * 6,
-            _ => 0,
-        }
+    /// points above the par needed for the bonus (three of each with 63)
+    fn upper_above_bonus(&self, rules: &RuleSet) -> i32 {
+        let face = match self.0 {
+            Ones => 1,
+            Twos => 2,
+            Threes => 3,
+            Fours => 4,
+            Fives => 5,
+            Sixes => 6,
+            _ => return 0,
+        };
+        self.1 as i32 - face * rules.bonus_reach as i32 / 21
     }
 }
 
 pub trait DiceCategory {
-    fn is_category(&self, category: &Category) -> bool;
-    fn value_of_category(&self, category: &Category) -> Value;
-    fn entry_of_category(&self, category: Category) -> Entry;
-    fn possible_entries(&self) -> HashMap<Category, u8>;
-    fn possible_entries_sorted(&self) -> Vec<Entry>;
+    fn is_category(&self, rules: &RuleSet, category: &Category) -> bool;
+    fn value_of_category(&self, rules: &RuleSet, category: &Category) -> Value;
+    fn joker_value_of_category(&self, rules: &RuleSet, category: &Category) -> Value;
+    fn entry_of_category(&self, rules: &RuleSet, category: Category) -> Entry;
+    fn possible_entries(&self, rules: &RuleSet) -> HashMap<Category, u8>;
+    fn possible_entries_sorted(&self, rules: &RuleSet) -> Vec<Entry>;
 }
 
 impl DiceCategory for Dice {
-    fn is_category(&self, category: &Category) -> bool {
+    fn is_category(&self, rules: &RuleSet, category: &Category) -> bool {
         match category {
             Ones => self.number_count(1) > 0,
             Twos => self.number_count(2) > 0,
@@ -95,17 +119,31 @@ impl DiceCategory for Dice {
             Fours => self.number_count(4) > 0,
             Fives => self.number_count(5) > 0,
             Sixes => self.number_count(6) > 0,
-            ThreeOfAKind => is_three_of_a_kind(self),
-            FourOfAKind => is_four_of_a_kind(self),
-            FullHouse => is_full_house(self),
-            SmallStraight => is_small_straight(self),
-            LargeStraght => is_large_straight(self),
-            Yahtzee => is_yahtzee(self),
+            OnePair => best_combination(rules, self, &[2]).is_some(),
+            TwoPairs => best_combination(rules, self, &[2, 2]).is_some(),
+            ThreePairs => best_combination(rules, self, &[2, 2, 2]).is_some(),
+            ThreeOfAKind => is_three_of_a_kind(rules, self),
+            FourOfAKind => is_four_of_a_kind(rules, self),
+            FiveOfAKind => best_combination(rules, self, &[5]).is_some(),
+            FullHouse => is_full_house(rules, self),
+            Castle => best_combination(rules, self, &[3, 3]).is_some(),
+            Tower => best_combination(rules, self, &[4, 2]).is_some(),
+            SmallStraight => match rules.variant {
+                Variant::Yahtzee | Variant::Generala => is_small_straight(self),
+                Variant::Yatzy => is_run(self, 1, 5),
+            },
+            LargeStraght => match rules.variant {
+                Variant::Yahtzee => is_large_straight(self),
+                Variant::Yatzy => is_run(self, 2, 6),
+                Variant::Generala => is_large_straight(self) || is_run(self, 3, 6) && self.number_count(1) == 1,
+            },
+            FullStraight => is_run(self, 1, 6),
+            Yahtzee | DoubleYahtzee => is_yahtzee(rules, self),
             Chance => true,
         }
     }
 
-    fn value_of_category(&self, category: &Category) -> Value {
+    fn value_of_category(&self, rules: &RuleSet, category: &Category) -> Value {
         match category {
             Ones => self.number_count(1),
             Twos => self.number_count(2) * 2,
@@ -113,39 +151,94 @@ impl DiceCategory for Dice {
             Fours => self.number_count(4) * 4,
             Fives => self.number_count(5) * 5,
             Sixes => self.number_count(6) * 6,
-            ThreeOfAKind => self.is_category(category) as Value * self.value(),
-            FourOfAKind => self.is_category(category) as Value * self.value(),
-            FullHouse => self.is_category(category) as Value * FULL_HOUSE_VALUE,
-            SmallStraight => self.is_category(category) as Value * SMALL_STRAIGHT_VALUE,
-            LargeStraght => self.is_category(category) as Value * LARGE_STRAIGHT_VALUE,
-            Yahtzee => self.is_category(category) as Value * YAHTZEE_VALUE,
-            Chance => self.is_category(category) as Value * self.value(),
+            OnePair => best_combination(rules, self, &[2]).unwrap_or(0),
+            TwoPairs => best_combination(rules, self, &[2, 2]).unwrap_or(0),
+            ThreePairs => best_combination(rules, self, &[2, 2, 2]).unwrap_or(0),
+            ThreeOfAKind if rules.variant == Variant::Yatzy => {
+                best_combination(rules, self, &[3]).unwrap_or(0)
+            },
+            FourOfAKind if rules.variant == Variant::Yatzy => {
+                best_combination(rules, self, &[4]).unwrap_or(0)
+            },
+            ThreeOfAKind => self.is_category(rules, category) as Value * self.value(),
+            FourOfAKind if rules.variant == Variant::Generala => {
+                self.is_category(rules, category) as Value * rules.poker_value
+            },
+            FourOfAKind => self.is_category(rules, category) as Value * self.value(),
+            FiveOfAKind => best_combination(rules, self, &[5]).unwrap_or(0),
+            FullHouse if rules.variant == Variant::Yatzy => {
+                best_combination(rules, self, &[3, 2]).unwrap_or(0)
+            },
+            FullHouse => self.is_category(rules, category) as Value * rules.full_house_value,
+            Castle => best_combination(rules, self, &[3, 3]).unwrap_or(0),
+            Tower => best_combination(rules, self, &[4, 2]).unwrap_or(0),
+            SmallStraight => self.is_category(rules, category) as Value * rules.small_straight_value,
+            LargeStraght => self.is_category(rules, category) as Value * rules.large_straight_value,
+            FullStraight => self.is_category(rules, category) as Value * rules.full_straight_value,
+            Yahtzee => self.is_category(rules, category) as Value * rules.yahtzee_value,
+            DoubleYahtzee => self.is_category(rules, category) as Value * rules.double_yahtzee_value,
+            Chance => self.is_category(rules, category) as Value * self.value(),
         }
     }
 
-    fn entry_of_category(&self, category: Category) -> Entry {
-        let value = self.value_of_category(&category); 
+    /// value when the dice are used as a Joker: Full House and the straights
+    /// score their full value even though the dice are a Yahtzee
+    fn joker_value_of_category(&self, rules: &RuleSet, category: &Category) -> Value {
+        match category {
+            FullHouse => rules.full_house_value,
+            SmallStraight => rules.small_straight_value,
+            LargeStraght => rules.large_straight_value,
+            _ => self.value_of_category(rules, category),
+        }
+    }
+
+    fn entry_of_category(&self, rules: &RuleSet, category: Category) -> Entry {
+        let value = self.value_of_category(rules, &category); 
         (category, value)
     }
 
-    fn possible_entries(&self) -> HashMap<Category, u8> {
+    fn possible_entries(&self, rules: &RuleSet) -> HashMap<Category, u8> {
         let mut map = HashMap::new();
-        for &cat in Category::into_iter() {
-            map.insert(cat, self.value_of_category(&cat));
+        for &cat in rules.categories.iter() {
+            map.insert(cat, self.value_of_category(rules, &cat));
         }
         map
     }
 
-    fn possible_entries_sorted(&self) -> Vec<Entry> {
-        let mut res = self.possible_entries().iter().map(|(&c, &e)| (c, e)).collect::<Vec<_>>();
+    fn possible_entries_sorted(&self, rules: &RuleSet) -> Vec<Entry> {
+        let mut res = self.possible_entries(rules).iter().map(|(&c, &e)| (c, e)).collect::<Vec<_>>();
         res.sort_unstable_by(|a, b| a.1.cmp(&b.1).reverse());
         res
     }
 }
 
 
-fn is_three_of_a_kind(dice: &Dice) -> bool {
-    for x in 1..DICE_NUM+1 {
+/// Highest sum of dice forming groups of the given sizes, each group of a
+/// different face, e.g. `[3, 2]` for a full house. `None` if the groups can't be formed.
+fn best_combination(rules: &RuleSet, dice: &Dice, groups: &[u8]) -> Option<Value> {
+    fn best(rules: &RuleSet, dice: &Dice, groups: &[u8], used: &mut Vec<Die>) -> Option<Value> {
+        let (&size, rest) = match groups.split_first() {
+            Some(split) => split,
+            None => return Some(0),
+        };
+        let mut max = None;
+        for &x in &rules.dice_faces {
+            if used.contains(&x) || dice.number_count(x) < size {
+                continue
+            }
+            used.push(x);
+            if let Some(value) = best(rules, dice, rest, used) {
+                max = max.max(Some(size * x + value));
+            }
+            used.pop();
+        }
+        max
+    }
+    best(rules, dice, groups, &mut Vec::new())
+}
+
+fn is_three_of_a_kind(rules: &RuleSet, dice: &Dice) -> bool {
+    for &x in &rules.dice_faces {
         let x_count = dice.number_count(x);
         if x_count >= 3 {
             return true
@@ -154,8 +247,8 @@ fn is_three_of_a_kind(dice: &Dice) -> bool {
     false
 }
 
-fn is_four_of_a_kind(dice: &Dice) -> bool {
-    for x in 1..DICE_NUM+1 {
+fn is_four_of_a_kind(rules: &RuleSet, dice: &Dice) -> bool {
+    for &x in &rules.dice_faces {
         let x_count = dice.number_count(x);
         if x_count >= 4 {
             return true
@@ -164,9 +257,10 @@ fn is_four_of_a_kind(dice: &Dice) -> bool {
     false
 }
 
-fn is_full_house(dice: &Dice) -> bool {
-    for x in 1..6 {
-        for y in x+1..=6 {
+fn is_full_house(rules: &RuleSet, dice: &Dice) -> bool {
+    let max = rules.max_face();
+    for x in 1..max {
+        for y in x+1..=max {
             let x_count = dice.number_count(x);
             let y_count = dice.number_count(y);
             if (x_count >= 3 && y_count >= 2) || (y_count >= 3 && x_count >= 2) {
@@ -192,10 +286,15 @@ fn is_large_straight(dice: &Dice) -> bool {
         && (dice.number_count(1) == 1 || dice.number_count(6) == 1)
 }
 
-fn is_yahtzee(dice: &Dice) -> bool {
-    for x in 1..=6 {
+/// every face from `low` to `high` at least once
+fn is_run(dice: &Dice, low: Die, high: Die) -> bool {
+    (low..=high).all(|x| dice.number_count(x) >= 1)
+}
+
+fn is_yahtzee(rules: &RuleSet, dice: &Dice) -> bool {
+    for &x in &rules.dice_faces {
         let x_count = dice.number_count(x);
-        if x_count == DICE_NUM {
+        if x_count == rules.dice_num {
             return true
         }
     }
@@ -219,7 +318,7 @@ mod tests {
     }
 
     fn small_straights() -> Vec<Dice> {
-        let straights = vec![vec![1,2,3,4], vec![2,3,4,5], vec![3,4,5,6]];
+        let straights = [vec![1,2,3,4], vec![2,3,4,5], vec![3,4,5,6]];
         let mut small_straights = Vec::new();
         for i in 1..=6 {
             for mut s in straights.iter().cloned() {
@@ -242,31 +341,45 @@ mod tests {
     fn yahtzees() -> Vec<Dice> {
         let mut yahtzees = Vec::new();
         for i in 1..=6 {
-            yahtzees.push(iter::repeat(i).take(DICE_NUM as usize).collect());
+            yahtzees.push(iter::repeat_n(i, RuleSet::default().dice_num as usize).collect());
         }
         yahtzees
     }
 
     #[test]
     fn test_is_full_house() {
+        let rules = RuleSet::default();
         for i in full_houses() {
-            assert!(is_full_house(&i));
+            assert!(is_full_house(&rules, &i));
         }
         for i in small_straights() {
-            assert!(!is_full_house(&i));
+            assert!(!is_full_house(&rules, &i));
         }
         for i in large_straights() {
-            assert!(!is_full_house(&i));
+            assert!(!is_full_house(&rules, &i));
         }
         for i in yahtzees() {
-            assert!(!is_full_house(&i));
+            assert!(!is_full_house(&rules, &i));
         }
-        assert!(!is_full_house(&vec![2,2,3,4,5]));
-        assert!(!is_full_house(&vec![2,3,4,4,5]));
-        assert!(!is_full_house(&vec![2,3,4,5,5]));
-        assert!(!is_full_house(&vec![1,2,3,4,5]));
-        assert!(!is_full_house(&vec![2,3,4,5,6]));
-        assert!(!is_full_house(&vec![2,6,4,5,3]));
+        assert!(!is_full_house(&rules, &vec![2,2,3,4,5]));
+        assert!(!is_full_house(&rules, &vec![2,3,4,4,5]));
+        assert!(!is_full_house(&rules, &vec![2,3,4,5,5]));
+        assert!(!is_full_house(&rules, &vec![1,2,3,4,5]));
+        assert!(!is_full_house(&rules, &vec![2,3,4,5,6]));
+        assert!(!is_full_house(&rules, &vec![2,6,4,5,3]));
+    }
+
+    #[test]
+    fn test_is_three_of_a_kind() {
+        let rules = RuleSet::default();
+        for i in yahtzees() {
+            assert!(is_three_of_a_kind(&rules, &i));
+        }
+        for i in full_houses() {
+            assert!(is_three_of_a_kind(&rules, &i));
+        }
+        assert!(is_three_of_a_kind(&rules, &vec![1,2,6,6,6]));
+        assert!(!is_three_of_a_kind(&rules, &vec![1,2,5,6,6]));
     }
 
     #[test]
@@ -310,19 +423,76 @@ mod tests {
 
     #[test]
     fn test_is_yahtzee() {
+        let rules = RuleSet::default();
         for i in yahtzees() {
-            assert!(is_yahtzee(&i));
+            assert!(is_yahtzee(&rules, &i));
         }
         for i in full_houses() {
-            assert!(!is_yahtzee(&i));
+            assert!(!is_yahtzee(&rules, &i));
         }
         for i in small_straights() {
-            assert!(!is_yahtzee(&i));
+            assert!(!is_yahtzee(&rules, &i));
         }
         for i in large_straights() {
-            assert!(!is_yahtzee(&i));
+            assert!(!is_yahtzee(&rules, &i));
         }
-        assert!(!is_yahtzee(&vec![1,1,1,1,5]));
-        assert!(!is_yahtzee(&vec![5,1,1,1,1]));
+        assert!(!is_yahtzee(&rules, &vec![1,1,1,1,5]));
+        assert!(!is_yahtzee(&rules, &vec![5,1,1,1,1]));
+    }
+
+    #[test]
+    fn test_yatzy_values() {
+        let rules = RuleSet::yatzy();
+        let dice = vec![2, 2, 5, 5, 5];
+        assert_eq!(dice.value_of_category(&rules, &OnePair), 10);
+        assert_eq!(dice.value_of_category(&rules, &TwoPairs), 14);
+        assert_eq!(dice.value_of_category(&rules, &ThreeOfAKind), 15);
+        assert_eq!(dice.value_of_category(&rules, &FourOfAKind), 0);
+        assert_eq!(dice.value_of_category(&rules, &FullHouse), 19);
+        assert_eq!(vec![3, 3, 3, 3, 1].value_of_category(&rules, &FourOfAKind), 12);
+        assert_eq!(vec![4, 4, 4, 4, 4].value_of_category(&rules, &TwoPairs), 0);
+    }
+
+    #[test]
+    fn test_yatzy_straights() {
+        let rules = RuleSet::yatzy();
+        let small = vec![1, 2, 3, 4, 5];
+        let large = vec![2, 3, 4, 5, 6];
+        assert_eq!(small.value_of_category(&rules, &SmallStraight), 15);
+        assert_eq!(small.value_of_category(&rules, &LargeStraght), 0);
+        assert_eq!(large.value_of_category(&rules, &SmallStraight), 0);
+        assert_eq!(large.value_of_category(&rules, &LargeStraght), 20);
+        assert_eq!(vec![1, 2, 3, 4, 6].value_of_category(&rules, &SmallStraight), 0);
+    }
+
+    #[test]
+    fn test_maxi_yatzy_values() {
+        let rules = RuleSet::maxi_yatzy();
+        let dice = vec![2, 2, 5, 5, 5, 5];
+        assert_eq!(dice.value_of_category(&rules, &TwoPairs), 14);
+        assert_eq!(dice.value_of_category(&rules, &ThreePairs), 0);
+        assert_eq!(dice.value_of_category(&rules, &FourOfAKind), 20);
+        assert_eq!(dice.value_of_category(&rules, &FullHouse), 19);
+        assert_eq!(dice.value_of_category(&rules, &Castle), 0);
+        assert_eq!(dice.value_of_category(&rules, &Tower), 24);
+        assert_eq!(vec![1, 1, 3, 3, 6, 6].value_of_category(&rules, &ThreePairs), 20);
+        assert_eq!(vec![2, 2, 2, 6, 6, 6].value_of_category(&rules, &Castle), 24);
+        assert_eq!(vec![2, 2, 2, 6, 6, 6].value_of_category(&rules, &FullHouse), 22);
+        assert_eq!(vec![1, 2, 3, 4, 5, 6].value_of_category(&rules, &FullStraight), 21);
+        assert_eq!(vec![1, 2, 3, 4, 5, 5].value_of_category(&rules, &SmallStraight), 15);
+        assert_eq!(vec![3, 3, 3, 3, 3, 3].value_of_category(&rules, &Yahtzee), 100);
+        assert_eq!(vec![3, 3, 3, 3, 3, 1].value_of_category(&rules, &Yahtzee), 0);
+        assert_eq!(vec![3, 3, 3, 3, 3, 1].value_of_category(&rules, &FiveOfAKind), 15);
+    }
+
+    #[test]
+    fn test_generala_values() {
+        let rules = RuleSet::generala();
+        assert_eq!(vec![1, 3, 4, 5, 6].value_of_category(&rules, &LargeStraght), 20);
+        assert_eq!(vec![2, 3, 4, 5, 6].value_of_category(&rules, &LargeStraght), 20);
+        assert_eq!(vec![1, 2, 4, 5, 6].value_of_category(&rules, &LargeStraght), 0);
+        assert_eq!(vec![2, 2, 2, 2, 6].value_of_category(&rules, &FourOfAKind), 40);
+        assert_eq!(vec![2, 2, 2, 6, 6].value_of_category(&rules, &FullHouse), 30);
+        assert_eq!(vec![6, 6, 6, 6, 6].value_of_category(&rules, &DoubleYahtzee), 100);
     }
 }
@@ -0,0 +1,215 @@
+use ai::YahtzeeAI;
+use dice::Dice;
+use player::Player;
+use solver::{Solver, load_or_solve, table_warning};
+use strategy::StrategyTable;
+use rules::RuleSet;
+use table::TableView;
+use types::ColumnEntry;
+use std::cmp::Ordering;
+use std::sync::OnceLock;
+
+/// points are worth a little, so that a game that is sure to be won or lost
+/// is still played for a high score
+const SCORE_WEIGHT: f64 = 1e-9;
+
+/// Plays a single free column for the chance to beat all opponents instead
+/// of the highest expected score: behind it gambles, ahead it plays safe.
+/// Final scores are taken as normal distributions with the mean and the
+/// variance the solver gives for the state of the card. Without opponents
+/// it plays like the `OptimalAI`, for other rules like a hard `YahtzeeAI`.
+pub struct WinningAI {
+    name: String,
+    table: Option<StrategyTable>,
+    solver: OnceLock<Option<Solver>>,
+    fallback: YahtzeeAI,
+}
+
+impl WinningAI {
+    pub fn new(name: &str) -> WinningAI {
+        WinningAI { name: String::from(name), table: None, solver: OnceLock::new(), fallback: YahtzeeAI::new() }
+    }
+
+    /// a player that reads the expected scores and variances from the table
+    pub fn with_table(name: &str, table: StrategyTable) -> WinningAI {
+        WinningAI { table: Some(table), ..WinningAI::new(name) }
+    }
+
+    /// a player with a solved solver for the rules
+    pub fn with_solver(name: &str, mut solver: Solver) -> WinningAI {
+        if !solver.has_variances() {
+            solver.solve_variances();
+        }
+        let player = WinningAI::new(name);
+        player.solver.set(Some(solver)).ok();
+        player
+    }
+
+    /// loads or solves once, the warning if the table could not be used
+    fn init(&self, rules: &RuleSet) -> Option<String> {
+        let mut warning = None;
+        self.solver.get_or_init(|| {
+            let (solver, error) = load_or_solve(self.table.as_ref(), rules);
+            warning = table_warning(self.table.as_ref(), error);
+            solver.map(|mut solver| {
+                if !solver.has_variances() {
+                    solver.solve_variances();
+                }
+                solver
+            })
+        });
+        warning
+    }
+
+    fn solver(&self, rules: &RuleSet) -> Option<&Solver> {
+        self.init(rules);
+        self.solver.get()?.as_ref().filter(|solver| solver.rules() == rules && solver.is_solved())
+    }
+
+    /// the best box of every outcome of the dice with what it is worth
+    fn entries(solver: &Solver, table: &TableView) -> Vec<Option<(usize, f64)>> {
+        let card = table.card();
+        let state = solver.state(card.column(0));
+        let others: Vec<(f64, f64)> = table.opponents().into_iter().map(|seat| {
+            let card = &table.state(seat).card;
+            let state = solver.state(card.column(0));
+            (card.value() as f64 + solver.value(state), solver.variance(state))
+        }).collect();
+        let boxes = solver.rules().categories.len();
+        (0..solver.tosses().outcomes().len()).map(|outcome| {
+            (0..boxes)
+                .filter_map(|cat| solver.write(state, outcome, cat).map(|(points, next)| {
+                    let mean = card.value() as f64 + points + solver.value(next);
+                    (cat, win_chance(mean, solver.variance(next), &others) + SCORE_WEIGHT * mean)
+                }))
+                .fold(None, |best: Option<(usize, f64)>, (cat, value)| match best {
+                    Some((_, best_value)) if best_value >= value => best,
+                    _ => Some((cat, value)),
+                })
+        }).collect()
+    }
+}
+
+/// Chance that a score around the mean beats the scores of all others,
+/// every score is normal and independent of the others.
+fn win_chance(mean: f64, variance: f64, others: &[(f64, f64)]) -> f64 {
+    others.iter().map(|&(other, other_variance)| {
+        let spread = (variance + other_variance).sqrt();
+        if spread > 1e-9 {
+            normal_cdf((mean - other) / spread)
+        } else {
+            match mean.partial_cmp(&other) {
+                Some(Ordering::Greater) => 1f64,
+                Some(Ordering::Less) => 0f64,
+                _ => 0.5,
+            }
+        }
+    }).product()
+}
+
+/// the standard normal distribution, Abramowitz and Stegun 7.1.26
+fn normal_cdf(x: f64) -> f64 {
+    let z = x.abs() / 2f64.sqrt();
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let tail = 0.5 * poly * (-z * z).exp();
+    if x < 0.0 { tail } else { 1.0 - tail }
+}
+
+impl Player for WinningAI {
+    fn decide_keep(&self, table: &TableView, remaining_tosses: u8, dice: &Dice) -> Dice {
+        let solver = match self.solver(table.rules()) {
+            Some(solver) => solver,
+            None => return self.fallback.decide_keep(table, remaining_tosses, dice),
+        };
+        let values = WinningAI::entries(solver, table).into_iter()
+            .map(|entry| entry.map_or(0f64, |(_, value)| value))
+            .collect();
+        match solver.tosses().best_keep(values, remaining_tosses, dice) {
+            Some(keep) => keep,
+            None => self.fallback.decide_keep(table, remaining_tosses, dice),
+        }
+    }
+
+    fn decide_entry(&self, table: &TableView, dice: &Dice) -> ColumnEntry {
+        let card = table.card();
+        let best = self.solver(table.rules()).and_then(|solver| {
+            let outcome = solver.tosses().outcome(dice)?;
+            WinningAI::entries(solver, table)[outcome].map(|(cat, _)| solver.rules().categories[cat])
+        });
+        match best {
+            Some(category) => card.entry_of_category(dice, 0, category),
+            None => self.fallback.decide_entry(table, dice),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// loads or solves the solver with its variances, which takes long
+    /// without a table
+    fn prepare(&self, rules: &RuleSet) -> Option<String> {
+        self.init(rules)
+    }
+
+    fn spec(&self) -> Option<String> {
+        match self.table {
+            Some(ref table) => Some(format!("winning:{}:{}", self.name, table.path().display())),
+            None => Some(format!("winning:{}", self.name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game::GameState;
+    use types::Category::*;
+
+    fn player(rules: &RuleSet) -> WinningAI {
+        let mut solver = Solver::new(rules).unwrap();
+        solver.solve();
+        WinningAI::with_solver("Winning", solver)
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_cdf(-1.96) - 0.025).abs() < 1e-4);
+        assert_eq!(win_chance(10.0, 0.0, &[(5.0, 0.0), (10.0, 0.0)]), 0.5);
+    }
+
+    #[test]
+    fn test_prepare() {
+        let rules = RuleSet { categories: vec![Yahtzee, Chance], ..RuleSet::yahtzee() };
+        let player = WinningAI::new("Winning");
+        assert_eq!(player.prepare(&rules), None);
+        assert!(player.solver.get().is_some_and(|solver| solver.as_ref().is_some_and(Solver::has_variances)));
+    }
+
+    #[test]
+    fn test_behind_and_ahead() {
+        let rules = RuleSet { categories: vec![Yahtzee, Chance], ..RuleSet::yahtzee() };
+        let player = player(&rules);
+        let mut states = vec![GameState::new(&rules), GameState::new(&rules)];
+        let straight = vec![1, 2, 3, 4, 5];
+        // the most points come from scratching Yahtzee and a later Chance,
+        // but that never beats a Chance of 30
+        states[1].write_entry(vec![6, 6, 6, 6, 6], (0, (Chance, 30)));
+        states[1].write_entry(vec![1, 2, 3, 4, 6], (0, (Yahtzee, 0)));
+        let table = TableView::new(&states, &[], 0, 2);
+        assert_eq!(player.decide_entry(&table, &straight), (0, (Chance, 15)));
+        // ahead it takes the points
+        states[1] = GameState::new(&rules);
+        states[1].write_entry(vec![1, 1, 1, 1, 1], (0, (Chance, 5)));
+        states[1].write_entry(vec![1, 2, 3, 4, 6], (0, (Yahtzee, 0)));
+        let table = TableView::new(&states, &[], 0, 2);
+        assert_eq!(player.decide_entry(&table, &straight), (0, (Yahtzee, 0)));
+        // alone it plays for points
+        let table = TableView::new(&states[..1], &[], 0, 0);
+        assert_eq!(player.decide_entry(&table, &straight), (0, (Yahtzee, 0)));
+        assert_eq!(player.decide_keep(&table, 2, &vec![6, 6, 6, 6, 1]), vec![6, 6, 6, 6]);
+    }
+}